@@ -0,0 +1,320 @@
+use shader_post_processing_example::headless::HeadlessRenderer;
+use shader_post_processing_example::lut::CubeLut;
+use shader_post_processing_example::preset::Preset;
+use shader_post_processing_example::Globals;
+use std::path::{Path, PathBuf};
+
+const USAGE: &str = "\
+applies post processing effects to image files
+
+usage: shader-post-processing-batch --effect <name>[,<name>...] [options] <input>...
+
+options:
+    --effect <names>     comma separated effects that are applied in order
+    --time <seconds>     animation time passed to the effects [default: 0]
+    --cursor <x>,<y>     cursor position from -1 to 1, (-1,-1) is the top left corner [default: 0,0]
+    --output <dir>       directory the processed images are written to [default: output]
+    --effect-file <path> adds the effect of a WGSL file with `// @param` annotations, can be repeated
+    --param <effect>.<name>=<value>[,<value>...]
+                         overrides a parameter of an effect, can be repeated
+    --preset <path>      applies the effects, parameters, cursor and time of a preset file,
+                         the options after it add to or override them
+    --lut <effect>=<path>
+                         replaces the lookup table of an effect with a .cube file, e.g. color_grading=look.cube
+    --list               print the available effects and their parameters
+    --fallback           use the software adapter of the platform
+    --help               print this message";
+
+struct Arguments {
+    /// applied in the given order after the effect files are loaded, so presets can use their effects
+    look: Vec<LookOption>,
+    output_dir: PathBuf,
+    effect_files: Vec<PathBuf>,
+    /// effect names and the .cube files of their lookup tables
+    luts: Vec<(String, PathBuf)>,
+    list: bool,
+    force_fallback_adapter: bool,
+    inputs: Vec<PathBuf>,
+}
+
+/// an option that selects the effects or changes their parameters, the cursor or the time
+enum LookOption {
+    Effects(Vec<String>),
+    Time(f32),
+    Cursor((f32, f32)),
+    Parameter(Parameter),
+    /// overrides the options before it
+    Preset(PathBuf),
+}
+
+#[derive(Clone)]
+struct Parameter {
+    effect: String,
+    name: String,
+    values: Vec<f32>,
+}
+
+fn main() {
+    env_logger::init();
+
+    let arguments = match parse_arguments(std::env::args().skip(1)) {
+        Ok(Some(arguments)) => arguments,
+        Ok(None) => {
+            println!("{USAGE}");
+            return;
+        }
+        Err(error) => {
+            eprintln!("error: {error}\n\n{USAGE}");
+            std::process::exit(2);
+        }
+    };
+
+    if let Err(error) = process(&arguments) {
+        eprintln!("error: {error}");
+        std::process::exit(1);
+    }
+}
+
+/// returns `None` if the usage was requested
+fn parse_arguments(mut args: impl Iterator<Item = String>) -> Result<Option<Arguments>, String> {
+    let mut look = Vec::new();
+    let mut output_dir = PathBuf::from("output");
+    let mut effect_files = Vec::new();
+    let mut luts = Vec::new();
+    let mut list = false;
+    let mut force_fallback_adapter = false;
+    let mut inputs = Vec::new();
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or_else(|| format!("missing value for {name}"));
+        match arg.as_str() {
+            "--help" | "-h" => return Ok(None),
+            "--effect" => look.push(LookOption::Effects(
+                value("--effect")?.split(',').map(|effect| effect.trim().to_string()).collect(),
+            )),
+            "--time" => {
+                let time_value = value("--time")?;
+                look.push(LookOption::Time(
+                    time_value.parse().map_err(|_| format!("invalid time '{time_value}'"))?,
+                ));
+            }
+            "--cursor" => {
+                let cursor_value = value("--cursor")?;
+                let cursor = cursor_value
+                    .split_once(',')
+                    .and_then(|(x, y)| Some((x.trim().parse().ok()?, y.trim().parse().ok()?)))
+                    .ok_or_else(|| format!("invalid cursor position '{cursor_value}', expected <x>,<y>"))?;
+                look.push(LookOption::Cursor(cursor));
+            }
+            "--output" => output_dir = PathBuf::from(value("--output")?),
+            "--effect-file" => effect_files.push(PathBuf::from(value("--effect-file")?)),
+            "--param" => look.push(LookOption::Parameter(parse_parameter(&value("--param")?)?)),
+            "--preset" => look.push(LookOption::Preset(PathBuf::from(value("--preset")?))),
+            "--lut" => {
+                let lut_value = value("--lut")?;
+                let (effect, path) = lut_value
+                    .split_once('=')
+                    .ok_or_else(|| format!("invalid lookup table '{lut_value}', expected <effect>=<path>"))?;
+                luts.push((effect.to_string(), PathBuf::from(path)));
+            }
+            "--list" => list = true,
+            "--fallback" => force_fallback_adapter = true,
+            _ if arg.starts_with("--") => return Err(format!("unknown option {arg}")),
+            _ => inputs.push(PathBuf::from(arg)),
+        }
+    }
+
+    let selects_effects = look
+        .iter()
+        .any(|option| matches!(option, LookOption::Effects(_) | LookOption::Preset(_)));
+    if !selects_effects && !list {
+        return Err("no effect given".to_string());
+    }
+    if inputs.is_empty() && !list {
+        return Err("no input images given".to_string());
+    }
+    // the outputs are named after the file stems, inputs with the same stem would overwrite each other
+    for (index, input) in inputs.iter().enumerate() {
+        let output = output_path(&output_dir, input);
+        if let Some(other) = inputs[..index].iter().find(|other| output_path(&output_dir, other) == output) {
+            return Err(format!(
+                "{} and {} would both be written to {}",
+                other.display(),
+                input.display(),
+                output.display()
+            ));
+        }
+    }
+
+    Ok(Some(Arguments {
+        look,
+        output_dir,
+        effect_files,
+        luts,
+        list,
+        force_fallback_adapter,
+        inputs,
+    }))
+}
+
+fn parse_parameter(parameter: &str) -> Result<Parameter, String> {
+    let invalid = || format!("invalid parameter '{parameter}', expected <effect>.<name>=<value>[,<value>...]");
+    let (path, values) = parameter.split_once('=').ok_or_else(invalid)?;
+    let (effect, name) = path.split_once('.').ok_or_else(invalid)?;
+    let values = values
+        .split(',')
+        .map(|value| value.trim().parse().map_err(|_| invalid()))
+        .collect::<Result<_, _>>()?;
+    Ok(Parameter {
+        effect: effect.to_string(),
+        name: name.to_string(),
+        values,
+    })
+}
+
+fn process(arguments: &Arguments) -> Result<(), Box<dyn std::error::Error>> {
+    let mut renderer = pollster::block_on(HeadlessRenderer::new(1, 1, arguments.force_fallback_adapter))?;
+    for effect_file in &arguments.effect_files {
+        renderer.load_effect_file(effect_file)?;
+    }
+
+    if arguments.list {
+        print_effects(&mut renderer);
+        return Ok(());
+    }
+
+    let mut effects = Vec::new();
+    let mut parameters = Vec::new();
+    let mut globals = Globals::new();
+    for option in &arguments.look {
+        match option {
+            LookOption::Effects(names) => effects.extend(names.iter().cloned()),
+            LookOption::Time(time) => globals.time = *time,
+            LookOption::Cursor((x, y)) => (globals.cursor_x, globals.cursor_y) = (*x, *y),
+            LookOption::Parameter(parameter) => parameters.push(parameter.clone()),
+            LookOption::Preset(path) => {
+                let preset = Preset::load(path, renderer.effect_registry()).map_err(|error| format!("{}: {error}", path.display()))?;
+                effects = preset.effects;
+                parameters.extend(preset.parameters.into_iter().map(|parameter| Parameter {
+                    effect: parameter.effect,
+                    name: parameter.name,
+                    values: parameter.values,
+                }));
+                if let Some((x, y)) = preset.cursor {
+                    (globals.cursor_x, globals.cursor_y) = (x, y);
+                }
+                if let Some(time) = preset.time {
+                    globals.time = time;
+                }
+            }
+        }
+    }
+
+    let effect_indices = effects
+        .iter()
+        .map(|effect| {
+            renderer.effect_index(effect).ok_or_else(|| {
+                format!(
+                    "unknown effect '{effect}', available effects: {}",
+                    renderer.effect_names().collect::<Vec<_>>().join(", ")
+                )
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    for parameter in &parameters {
+        let effect_index = renderer
+            .effect_index(&parameter.effect)
+            .ok_or_else(|| format!("unknown effect '{}' in --param", parameter.effect))?;
+        renderer.set_parameter(effect_index, &parameter.name, &parameter.values)?;
+    }
+
+    for (effect, path) in &arguments.luts {
+        let effect_index = renderer
+            .effect_index(effect)
+            .ok_or_else(|| format!("unknown effect '{effect}' in --lut"))?;
+        let source = std::fs::read_to_string(path).map_err(|error| format!("could not read {}: {error}", path.display()))?;
+        let lut = CubeLut::parse(&source).map_err(|error| format!("{}: {error}", path.display()))?;
+        if !renderer
+            .set_lut(effect_index, &lut)
+            .map_err(|error| format!("{}: {error}", path.display()))?
+        {
+            return Err(format!("the effect '{effect}' does not use a lookup table").into());
+        }
+    }
+
+    std::fs::create_dir_all(&arguments.output_dir)?;
+
+    for input in &arguments.inputs {
+        let img = image::open(input).map_err(|error| format!("could not read {}: {error}", input.display()))?;
+        renderer
+            .set_scene_image(&img, input.to_str())
+            .map_err(|error| format!("{}: {error}", input.display()))?;
+
+        let output = output_path(&arguments.output_dir, input);
+        renderer
+            .render(&effect_indices, &globals)?
+            .save(&output)
+            .map_err(|error| format!("could not write {}: {error}", output.display()))?;
+        println!("{} -> {}", input.display(), output.display());
+    }
+
+    Ok(())
+}
+
+fn print_effects(renderer: &mut HeadlessRenderer) {
+    let names: Vec<String> = renderer.effect_names().map(str::to_string).collect();
+    for (effect_index, name) in names.iter().enumerate() {
+        println!("{name}");
+        let Some(parameters) = renderer.parameters(effect_index) else {
+            continue;
+        };
+        for parameter in &parameters.layout().parameters {
+            let range = |bound: Option<f32>| bound.map_or("-".to_string(), |bound| bound.to_string());
+            println!(
+                "    {} {:?} min={} max={} value={:?}",
+                parameter.name,
+                parameter.ty,
+                range(parameter.min),
+                range(parameter.max),
+                parameters.get(&parameter.name).unwrap_or_default()
+            );
+        }
+    }
+}
+
+/// the processed image keeps the file name of the input but is always stored as png
+fn output_path(output_dir: &Path, input: &Path) -> PathBuf {
+    let mut file_name = input.file_stem().unwrap_or(input.as_os_str()).to_os_string();
+    file_name.push(".png");
+    output_dir.join(file_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn arguments(args: &[&str]) -> Result<Option<Arguments>, String> {
+        parse_arguments(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn outputs_are_png_files_named_after_the_input() {
+        assert_eq!(output_path(Path::new("out"), Path::new("photos/x.jpg")), PathBuf::from("out/x.png"));
+        assert_eq!(
+            output_path(Path::new("out"), Path::new("x.tar.png")),
+            PathBuf::from("out/x.tar.png")
+        );
+    }
+
+    #[test]
+    fn inputs_with_the_same_stem_are_rejected() {
+        for inputs in [["x.png", "x.jpg"], ["a/x.png", "b/x.png"]] {
+            let error = arguments(&["--effect", "droplet", "--output", "out", inputs[0], inputs[1]])
+                .err()
+                .expect("the outputs are the same");
+            assert!(error.contains("out/x.png"), "{}", error);
+        }
+        assert!(arguments(&["--effect", "droplet", "a/x.png", "b/y.png"]).is_ok());
+    }
+}
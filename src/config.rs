@@ -0,0 +1,479 @@
+//! the setup of the demo described in a TOML file, loaded with `--config <path>`
+//!
+//! ```toml
+//! # relative paths start at the directory of the config file
+//! scene = "photo.png"
+//! effects = ["wave_distortion", "droplet"]
+//! # "linear" or "nearest" filtering of the scene image
+//! sampler = "nearest"
+//! # the preset files that are cycled with the P key, see `preset`
+//! presets = "presets"
+//!
+//! [window]
+//! width = 800
+//! height = 220
+//!
+//! [parameters.droplet]
+//! fall_off_distance = 0.4
+//! ```
+//!
+//! The effects and parameters are checked against the registry of the post processor, errors name the line and the offending key.
+
+use crate::effect_registry::EffectRegistry;
+use crate::post_processor::PostProcessor;
+use serde::de::{self, DeserializeOwned, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use toml::Spanned;
+
+pub const DEFAULT_WINDOW_SIZE: (u32, u32) = (800, 220);
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Config {
+    /// the image file the effects are applied to, the embedded logo if not set
+    pub scene: Option<PathBuf>,
+    /// the names of the effects that are applied in order, the first effect if empty
+    pub effects: Vec<String>,
+    pub parameters: Vec<ParameterValue>,
+    /// how the scene image is filtered when it is scaled to the window
+    pub sampler: Option<wgpu::FilterMode>,
+    pub window_size: Option<(u32, u32)>,
+    /// the directory of the preset files, `presets` in the working directory if not set
+    pub presets: Option<PathBuf>,
+}
+
+/// the value of a parameter of an effect, see `effect_parameters`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParameterValue {
+    pub effect: String,
+    pub name: String,
+    pub values: Vec<f32>,
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(std::io::Error),
+    /// `line` is 0 for errors that don't belong to a line, e.g. a missing key
+    Invalid {
+        line: usize,
+        key: String,
+        message: String,
+    },
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::Io(error) => write!(f, "could not read the config file, {error}"),
+            ConfigError::Invalid { line: 0, key, message } => write!(f, "{key}: {message}"),
+            ConfigError::Invalid { line, key, message } => write!(f, "line {line}: {key}: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl Config {
+    /// reads the config file, relative scene and preset paths are resolved against the directory of the file
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load(path: &std::path::Path, effect_registry: &EffectRegistry) -> Result<Self, ConfigError> {
+        let source = std::fs::read_to_string(path).map_err(ConfigError::Io)?;
+        let mut config = Self::parse(&source, effect_registry)?;
+        if let Some(dir) = path.parent() {
+            for relative_path in config.scene.iter_mut().chain(config.presets.iter_mut()) {
+                *relative_path = dir.join(&*relative_path);
+            }
+        }
+        Ok(config)
+    }
+
+    /// the effects and parameters have to be registered, e.g. `PostProcessor::effect_registry`
+    pub fn parse(source: &str, effect_registry: &EffectRegistry) -> Result<Self, ConfigError> {
+        let file: ConfigFile = deserialize(source)?;
+
+        let window_size = match file.window {
+            Some(WindowTable {
+                width: Some(width),
+                height: Some(height),
+            }) => Some((size(source, "window.width", width)?, size(source, "window.height", height)?)),
+            Some(WindowTable { width: Some(_), .. }) => {
+                return Err(invalid(
+                    table_line(source, "window"),
+                    "window.height",
+                    "missing, the size needs a width and a height",
+                ))
+            }
+            Some(WindowTable { height: Some(_), .. }) => {
+                return Err(invalid(
+                    table_line(source, "window"),
+                    "window.width",
+                    "missing, the size needs a width and a height",
+                ))
+            }
+            _ => None,
+        };
+
+        Ok(Config {
+            scene: file.scene,
+            effects: match file.effects {
+                Some(effect_names) => effects(source, effect_names, effect_registry)?,
+                None => Vec::new(),
+            },
+            parameters: parameters(source, file.parameters, effect_registry)?
+                .into_iter()
+                .map(|(_, parameter)| parameter)
+                .collect(),
+            sampler: file.sampler.map(|sampler| match sampler {
+                Sampler::Linear => wgpu::FilterMode::Linear,
+                Sampler::Nearest => wgpu::FilterMode::Nearest,
+            }),
+            window_size,
+            presets: file.presets,
+        })
+    }
+
+    /// selects the effects and sets their parameters, the key of an effect or parameter that the post processor doesn't have is returned
+    pub fn apply(&self, device: &wgpu::Device, queue: &wgpu::Queue, post_processor: &mut PostProcessor) -> Result<(), ConfigError> {
+        if !self.effects.is_empty() {
+            set_chain(device, queue, post_processor, &self.effects)?;
+        }
+        set_parameters(device, queue, post_processor, &self.parameters)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    scene: Option<PathBuf>,
+    effects: Option<Spanned<Vec<Spanned<String>>>>,
+    sampler: Option<Sampler>,
+    presets: Option<PathBuf>,
+    window: Option<WindowTable>,
+    #[serde(default)]
+    parameters: ParameterTables,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Sampler {
+    Linear,
+    Nearest,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WindowTable {
+    width: Option<Spanned<u32>>,
+    height: Option<Spanned<u32>>,
+}
+
+/// the `[parameters.<effect>]` tables of a config or preset file
+pub(crate) type ParameterTables = BTreeMap<Spanned<String>, BTreeMap<Spanned<String>, Spanned<Numbers>>>;
+
+/// a finite number
+pub(crate) struct Number(pub f32);
+
+impl<'de> Deserialize<'de> for Number {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        finite(f64::deserialize(deserializer)?).map(Number)
+    }
+}
+
+/// a number or an array of numbers, e.g. the value of a parameter
+pub(crate) struct Numbers(pub Vec<f32>);
+
+impl<'de> Deserialize<'de> for Numbers {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(NumbersVisitor)
+    }
+}
+
+struct NumbersVisitor;
+
+impl<'de> Visitor<'de> for NumbersVisitor {
+    type Value = Numbers;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        formatter.write_str("a number or an array of numbers")
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<Numbers, E> {
+        self.visit_f64(value as f64)
+    }
+
+    fn visit_f64<E: de::Error>(self, value: f64) -> Result<Numbers, E> {
+        Ok(Numbers(vec![finite(value)?]))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Numbers, A::Error> {
+        let mut values = Vec::new();
+        while let Some(Number(value)) = seq.next_element()? {
+            values.push(value);
+        }
+        Ok(Numbers(values))
+    }
+}
+
+fn finite<E: de::Error>(value: f64) -> Result<f32, E> {
+    match value as f32 {
+        value if value.is_finite() => Ok(value),
+        _ => Err(E::custom(format!("expected a finite number but got {value}"))),
+    }
+}
+
+/// selects the effects by name
+pub(crate) fn set_chain(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    post_processor: &mut PostProcessor,
+    effects: &[String],
+) -> Result<(), ConfigError> {
+    let effect_indices = effects
+        .iter()
+        .enumerate()
+        .map(|(index, name)| {
+            post_processor
+                .effect_index(name)
+                .ok_or_else(|| invalid(0, format!("effects[{index}]"), format!("unknown effect '{name}'")))
+        })
+        .collect::<Result<_, _>>()?;
+    post_processor
+        .set_chain(device, queue, effect_indices)
+        .expect("the indices were looked up by name");
+    Ok(())
+}
+
+pub(crate) fn set_parameters(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    post_processor: &mut PostProcessor,
+    parameters: &[ParameterValue],
+) -> Result<(), ConfigError> {
+    for parameter in parameters {
+        let key = format!("parameters.{}.{}", parameter.effect, parameter.name);
+        let effect_index = post_processor
+            .effect_index(&parameter.effect)
+            .ok_or_else(|| invalid(0, &key, format!("unknown effect '{}'", parameter.effect)))?;
+        post_processor
+            .create_effect(device, queue, effect_index)
+            .expect("the index was looked up by name")
+            .parameters_mut()
+            .ok_or_else(|| invalid(0, &key, format!("{} has no parameters", parameter.effect)))?
+            .set(&parameter.name, &parameter.values)
+            .map_err(|error| invalid(0, &key, error.to_string()))?;
+    }
+    Ok(())
+}
+
+/// deserializes a config or preset file, a TOML error names the line and the key at its span
+pub(crate) fn deserialize<T: DeserializeOwned>(source: &str) -> Result<T, ConfigError> {
+    toml::from_str(source).map_err(|error| {
+        let message = error.message().replace('\n', ", ");
+        match error.span() {
+            Some(span) => invalid_at(source, span.start, key_at(source, span.start), message),
+            None => invalid(0, "", message),
+        }
+    })
+}
+
+/// the names of the effects in order, they have to be registered
+pub(crate) fn effects(
+    source: &str,
+    effects: Spanned<Vec<Spanned<String>>>,
+    effect_registry: &EffectRegistry,
+) -> Result<Vec<String>, ConfigError> {
+    if effects.get_ref().is_empty() {
+        return Err(invalid_at(source, effects.span().start, "effects", "expected at least one effect"));
+    }
+    effects
+        .into_inner()
+        .into_iter()
+        .enumerate()
+        .map(|(index, name)| match effect_registry.index_of(name.get_ref()) {
+            Some(_) => Ok(name.into_inner()),
+            None => Err(invalid_at(
+                source,
+                name.span().start,
+                format!("effects[{index}]"),
+                format!("unknown effect '{}'", name.get_ref()),
+            )),
+        })
+        .collect()
+}
+
+/// the parameter values of the tables with their lines in the order of the file.
+/// The parameters of effects that are implemented in Rust are checked when they are set, they are not in the registry.
+pub(crate) fn parameters(
+    source: &str,
+    tables: ParameterTables,
+    effect_registry: &EffectRegistry,
+) -> Result<Vec<(usize, ParameterValue)>, ConfigError> {
+    let mut tables: Vec<_> = tables.into_iter().collect();
+    tables.sort_by_key(|(effect, _)| effect.span().start);
+
+    let mut parameters = Vec::new();
+    for (effect, table) in tables {
+        let effect_index = effect_registry.index_of(effect.get_ref()).ok_or_else(|| {
+            invalid_at(
+                source,
+                effect.span().start,
+                format!("parameters.{}", effect.get_ref()),
+                format!("unknown effect '{}'", effect.get_ref()),
+            )
+        })?;
+        let mut table: Vec<_> = table.into_iter().collect();
+        table.sort_by_key(|(name, _)| name.span().start);
+
+        for (name, values) in table {
+            let key = format!("parameters.{}.{}", effect.get_ref(), name.get_ref());
+            if let Some(descriptor) = effect_registry.descriptor(effect_index) {
+                let parameter = descriptor.parameters.get(name.get_ref()).ok_or_else(|| {
+                    invalid_at(
+                        source,
+                        name.span().start,
+                        &key,
+                        format!("unknown parameter of {}", effect.get_ref()),
+                    )
+                })?;
+                let (expected, count) = (parameter.ty.components(), values.get_ref().0.len());
+                if count != expected {
+                    return Err(invalid_at(
+                        source,
+                        values.span().start,
+                        &key,
+                        format!("expected {expected} values but got {count}"),
+                    ));
+                }
+            }
+            parameters.push((
+                line(source, name.span().start),
+                ParameterValue {
+                    effect: effect.get_ref().clone(),
+                    name: name.into_inner(),
+                    values: values.into_inner().0,
+                },
+            ));
+        }
+    }
+    Ok(parameters)
+}
+
+/// a size in pixels
+fn size(source: &str, key: &str, size: Spanned<u32>) -> Result<u32, ConfigError> {
+    match *size.get_ref() {
+        0 => Err(invalid_at(source, size.span().start, key, "expected a positive integer")),
+        size => Ok(size),
+    }
+}
+
+pub(crate) fn invalid(line: usize, key: impl Into<String>, message: impl Into<String>) -> ConfigError {
+    ConfigError::Invalid {
+        line,
+        key: key.into(),
+        message: message.into(),
+    }
+}
+
+/// an error at the byte offset of the source
+pub(crate) fn invalid_at(source: &str, offset: usize, key: impl Into<String>, message: impl Into<String>) -> ConfigError {
+    invalid(line(source, offset), key, message)
+}
+
+/// the line number of the byte offset, starting at 1
+fn line(source: &str, offset: usize) -> usize {
+    source[..offset.min(source.len())].matches('\n').count() + 1
+}
+
+/// the line of the key that declares the table, e.g. of its `[window]` header, 0 if the table is not declared
+fn table_line(source: &str, table: &str) -> usize {
+    match toml::from_str::<Keys>(source) {
+        Ok(Keys(keys)) => keys
+            .into_iter()
+            .find(|(_, key)| key == table)
+            .map_or(0, |(offset, _)| line(source, offset)),
+        Err(_) => 0,
+    }
+}
+
+/// the key of the value at the byte offset, which is the last key that starts before it.
+/// If the source is not valid TOML it is the key or table header at the start of the line.
+fn key_at(source: &str, offset: usize) -> String {
+    if let Ok(Keys(keys)) = toml::from_str::<Keys>(source) {
+        return keys
+            .into_iter()
+            .filter(|(start, _)| *start <= offset)
+            .max_by_key(|(start, _)| *start)
+            .map(|(_, key)| key)
+            .unwrap_or_default();
+    }
+    let offset = offset.min(source.len());
+    let line_start = source[..offset].rfind('\n').map_or(0, |index| index + 1);
+    let line_end = source[offset..].find('\n').map_or(source.len(), |index| offset + index);
+    let line = source[line_start..line_end].trim();
+    match line.strip_prefix('[') {
+        Some(header) => header.trim_matches(|c| c == '[' || c == ']').trim().to_string(),
+        None => line.split('=').next().unwrap_or_default().trim().to_string(),
+    }
+}
+
+/// the byte offset and the dotted path of every key of a document, e.g. `parameters.droplet.fall_off_distance`
+#[derive(Default)]
+struct Keys(Vec<(usize, String)>);
+
+impl<'de> Deserialize<'de> for Keys {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(KeysVisitor)
+    }
+}
+
+struct KeysVisitor;
+
+impl<'de> Visitor<'de> for KeysVisitor {
+    type Value = Keys;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        formatter.write_str("a TOML value")
+    }
+
+    fn visit_bool<E>(self, _value: bool) -> Result<Keys, E> {
+        Ok(Keys::default())
+    }
+
+    fn visit_i64<E>(self, _value: i64) -> Result<Keys, E> {
+        Ok(Keys::default())
+    }
+
+    fn visit_f64<E>(self, _value: f64) -> Result<Keys, E> {
+        Ok(Keys::default())
+    }
+
+    fn visit_str<E>(self, _value: &str) -> Result<Keys, E> {
+        Ok(Keys::default())
+    }
+
+    /// the keys of the inline tables in the array
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Keys, A::Error> {
+        let mut keys = Keys::default();
+        let mut index = 0;
+        while let Some(Keys(element_keys)) = seq.next_element()? {
+            keys.0
+                .extend(element_keys.into_iter().map(|(offset, key)| (offset, format!("[{index}].{key}"))));
+            index += 1;
+        }
+        Ok(keys)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Keys, A::Error> {
+        let mut keys = Keys::default();
+        while let Some(key) = map.next_key::<Spanned<String>>()? {
+            let Keys(value_keys) = map.next_value()?;
+            keys.0.extend(value_keys.into_iter().map(|(offset, value_key)| {
+                let separator = if value_key.starts_with('[') { "" } else { "." };
+                (offset, format!("{}{separator}{value_key}", key.get_ref()))
+            }));
+            keys.0.push((key.span().start, key.into_inner()));
+        }
+        Ok(keys)
+    }
+}
@@ -0,0 +1,41 @@
+use crate::effect_parameters::EffectParameters;
+use crate::post_processing::PostProcessing;
+use crate::shader_globals::Globals;
+
+/// a post processing effect that the `PostProcessingChain` applies to the output of the previous effect
+///
+/// `PostProcessing` implements it for the effects of a WGSL file. Implement it directly for effects that need
+/// their own textures and buffers, run passes that don't fit the `@pass` annotations or keep state between frames,
+/// e.g. the previous frame of a feedback effect.
+pub trait Effect {
+    /// called when the size of the render targets of the chain changed, size dependent resources are recreated here
+    fn resize(&mut self, device: &wgpu::Device, size: wgpu::Extent3d);
+
+    /// called once per frame before `encode`, uniforms are uploaded and state that is kept between frames is advanced here
+    fn update(&mut self, queue: &wgpu::Queue, globals: &Globals);
+
+    /// records the commands that read the src texture and write the result to the dst_view.
+    /// `src_bind_group` binds the texture and its sampler like `RenderTarget::bind_group`, the effects bind it at group 1.
+    fn encode(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        src_bind_group: &wgpu::BindGroup,
+        dst_view: &wgpu::TextureView,
+        globals_bind_group: &wgpu::BindGroup,
+    );
+
+    /// the parameters that can be changed by name, e.g. with `--param` of the batch tool
+    fn parameters(&self) -> Option<&EffectParameters> {
+        None
+    }
+
+    /// changed parameters are uploaded with the next call of `update`
+    fn parameters_mut(&mut self) -> Option<&mut EffectParameters> {
+        None
+    }
+
+    /// the effect as shader effect, which can reload its shader and replace its lookup table
+    fn as_post_processing_mut(&mut self) -> Option<&mut PostProcessing> {
+        None
+    }
+}
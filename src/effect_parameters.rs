@@ -0,0 +1,487 @@
+//! effect specific uniform parameters that are declared by annotations in the WGSL file of an effect:
+//!
+//! ```wgsl
+//! // @param strength f32 min=0 max=1 default=0.5
+//! // @param center vec2f default=0.5,0.5
+//! ```
+//!
+//! the annotations are parsed into a `ParameterLayout`, which generates the WGSL declaration of the uniform
+//! `parameters` at `@group(2) @binding(0)` with one field per annotation, e.g. `parameters.strength`.
+
+use wgpu::util::DeviceExt;
+
+pub const BIND_GROUP_LAYOUT: &wgpu::BindGroupLayoutDescriptor = &wgpu::BindGroupLayoutDescriptor {
+    label: Some("effect parameters bind group layout"),
+    entries: &[wgpu::BindGroupLayoutEntry {
+        binding: 0,
+        count: None,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Uniform,
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        visibility: wgpu::ShaderStages::FRAGMENT.union(wgpu::ShaderStages::COMPUTE),
+    }],
+};
+
+const ANNOTATION: &str = "@param";
+// WebGL needs 16 byte alignment
+const STRUCT_ALIGNMENT: usize = 16;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ParameterType {
+    F32,
+    Vec2,
+    Vec3,
+    Vec4,
+}
+
+impl ParameterType {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "f32" => Some(Self::F32),
+            "vec2f" | "vec2<f32>" => Some(Self::Vec2),
+            "vec3f" | "vec3<f32>" => Some(Self::Vec3),
+            "vec4f" | "vec4<f32>" => Some(Self::Vec4),
+            _ => None,
+        }
+    }
+
+    pub fn components(self) -> usize {
+        match self {
+            Self::F32 => 1,
+            Self::Vec2 => 2,
+            Self::Vec3 => 3,
+            Self::Vec4 => 4,
+        }
+    }
+
+    /// alignment in bytes as defined by the WGSL spec for the uniform address space
+    fn alignment(self) -> usize {
+        match self {
+            Self::F32 => 4,
+            Self::Vec2 => 8,
+            Self::Vec3 | Self::Vec4 => 16,
+        }
+    }
+
+    fn wgsl(self) -> &'static str {
+        match self {
+            Self::F32 => "f32",
+            Self::Vec2 => "vec2<f32>",
+            Self::Vec3 => "vec3<f32>",
+            Self::Vec4 => "vec4<f32>",
+        }
+    }
+}
+
+/// metadata of a single parameter
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParameterInfo {
+    pub name: String,
+    pub ty: ParameterType,
+    pub min: Option<f32>,
+    pub max: Option<f32>,
+    /// one value per component of `ty`, 0 moved into the range of min and max if the annotation has no default
+    pub default: Vec<f32>,
+    /// byte offset inside the uniform buffer
+    pub offset: usize,
+}
+
+impl ParameterInfo {
+    fn clamp(&self, value: f32) -> f32 {
+        let value = self.min.map_or(value, |min| value.max(min));
+        self.max.map_or(value, |max| value.min(max))
+    }
+}
+
+#[derive(Debug)]
+pub enum ParameterError {
+    InvalidAnnotation { line: usize, message: String },
+    UnknownParameter(String),
+    WrongComponentCount { name: String, expected: usize, actual: usize },
+}
+
+impl std::fmt::Display for ParameterError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParameterError::InvalidAnnotation { line, message } => {
+                write!(f, "line {line}: invalid {ANNOTATION} annotation, {message}")
+            }
+            ParameterError::UnknownParameter(name) => write!(f, "unknown parameter '{name}'"),
+            ParameterError::WrongComponentCount { name, expected, actual } => {
+                write!(f, "parameter '{name}' expects {expected} values but got {actual}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParameterError {}
+
+/// the uniform buffer layout generated from the `@param` annotations of a shader
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ParameterLayout {
+    pub parameters: Vec<ParameterInfo>,
+    /// size of the uniform buffer in bytes
+    pub size: usize,
+}
+
+impl ParameterLayout {
+    /// collects all `// @param <name> <type> [min=<value>] [max=<value>] [default=<value>[,<value>...]]` annotations
+    pub fn parse(source: &str) -> Result<Self, ParameterError> {
+        let mut parameters: Vec<ParameterInfo> = Vec::new();
+        let mut offset: usize = 0;
+
+        for (line_index, line) in source.lines().enumerate() {
+            let Some(annotation) = line.trim().strip_prefix("//").map(str::trim) else {
+                continue;
+            };
+            let Some(annotation) = strip_keyword(annotation, ANNOTATION) else {
+                continue;
+            };
+
+            let error = |message: String| ParameterError::InvalidAnnotation {
+                line: line_index + 1,
+                message,
+            };
+            let mut words = annotation.split_whitespace();
+
+            let name = words.next().ok_or_else(|| error("missing name".to_string()))?;
+            if !is_identifier(name) {
+                return Err(error(format!("'{name}' is not a valid identifier")));
+            }
+            if parameters.iter().any(|parameter| parameter.name == name) {
+                return Err(error(format!("'{name}' is declared twice")));
+            }
+
+            let type_name = words.next().ok_or_else(|| error(format!("missing type of '{name}'")))?;
+            let ty = ParameterType::parse(type_name).ok_or_else(|| error(format!("unsupported type '{type_name}'")))?;
+
+            let mut info = ParameterInfo {
+                name: name.to_string(),
+                ty,
+                min: None,
+                max: None,
+                default: Vec::new(),
+                offset: 0,
+            };
+
+            for option in words {
+                let (key, value) = option
+                    .split_once('=')
+                    .ok_or_else(|| error(format!("expected key=value but got '{option}'")))?;
+                let parse = |value: &str| {
+                    value
+                        .parse::<f32>()
+                        .map_err(|_| error(format!("invalid number '{value}' for {key}")))
+                };
+                match key {
+                    "min" => info.min = Some(parse(value)?),
+                    "max" => info.max = Some(parse(value)?),
+                    "default" => {
+                        info.default = value.split(',').map(parse).collect::<Result<_, _>>()?;
+                        if info.default.len() != ty.components() {
+                            return Err(error(format!("default of '{name}' needs {} values", ty.components())));
+                        }
+                    }
+                    _ => return Err(error(format!("unknown option '{key}'"))),
+                }
+            }
+            if let (Some(min), Some(max)) = (info.min, info.max) {
+                if min > max {
+                    return Err(error(format!("min {min} of '{name}' is greater than max {max}")));
+                }
+            }
+            if info.default.is_empty() {
+                // without a default the parameter starts at 0, or at the bound that is closest to it
+                info.default = vec![info.clamp(0.); ty.components()];
+            } else if let Some(value) = info.default.iter().find(|value| info.clamp(**value) != **value) {
+                return Err(error(format!("default {value} of '{name}' is outside of min and max")));
+            }
+
+            offset = offset.next_multiple_of(ty.alignment());
+            info.offset = offset;
+            offset += 4 * ty.components();
+
+            parameters.push(info);
+        }
+
+        Ok(Self {
+            parameters,
+            size: offset.next_multiple_of(STRUCT_ALIGNMENT),
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.parameters.is_empty()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&ParameterInfo> {
+        self.parameters.iter().find(|parameter| parameter.name == name)
+    }
+
+    /// the declaration of the `parameters` uniform, padded to a multiple of 16 bytes
+    pub fn wgsl_declarations(&self) -> String {
+        let mut fields: Vec<String> = self
+            .parameters
+            .iter()
+            .map(|parameter| format!("    {}: {},\n", parameter.name, parameter.ty.wgsl()))
+            .collect();
+
+        let end = self.parameters.last().map_or(0, |last| last.offset + 4 * last.ty.components());
+        for padding in 0..(self.size - end) / 4 {
+            fields.push(format!("    _padding{padding}: f32,\n"));
+        }
+
+        format!(
+            "struct Parameters {{\n    // WebGL needs 16 byte alignment\n{}}}\n@group(2) @binding(0)\nvar<uniform> parameters: Parameters;\n",
+            fields.concat()
+        )
+    }
+
+    /// the uniform buffer content with the default values
+    fn default_data(&self) -> Vec<u8> {
+        let mut data = vec![0; self.size];
+        for parameter in &self.parameters {
+            write_values(&mut data, parameter, &parameter.default);
+        }
+        data
+    }
+}
+
+pub(crate) fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|first| first.is_ascii_alphabetic() || first == '_') && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// the text after the keyword at the start of the comment, `None` if the comment starts with a longer word like `@parameters`
+pub(crate) fn strip_keyword<'a>(comment: &'a str, keyword: &str) -> Option<&'a str> {
+    comment
+        .strip_prefix(keyword)
+        .filter(|rest| rest.is_empty() || rest.starts_with(char::is_whitespace))
+}
+
+fn write_values(data: &mut [u8], parameter: &ParameterInfo, values: &[f32]) {
+    let bytes = &mut data[parameter.offset..parameter.offset + 4 * values.len()];
+    bytes.copy_from_slice(bytemuck::cast_slice(values));
+}
+
+/// the uniform buffer of an effect, bound at group 2 next to the globals and the input texture
+///
+/// the values are kept on the CPU and only uploaded to the GPU after they changed
+pub struct EffectParameters {
+    layout: ParameterLayout,
+    data: Vec<u8>,
+    changed: bool,
+    buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+}
+
+impl EffectParameters {
+    /// creates the parameters with their default values
+    pub fn new(device: &wgpu::Device, bind_group_layout: &wgpu::BindGroupLayout, layout: ParameterLayout) -> Self {
+        let data = layout.default_data();
+
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("effect parameters buffer"),
+            contents: &data,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("effect parameters bind group"),
+            layout: bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+        });
+
+        Self {
+            layout,
+            data,
+            changed: false,
+            buffer,
+            bind_group,
+        }
+    }
+
+    pub fn layout(&self) -> &ParameterLayout {
+        &self.layout
+    }
+
+    /// returns the current value of the parameter, one value per component
+    pub fn get(&self, name: &str) -> Option<Vec<f32>> {
+        let parameter = self.layout.get(name)?;
+        let bytes = &self.data[parameter.offset..parameter.offset + 4 * parameter.ty.components()];
+        Some(bytemuck::pod_collect_to_vec(bytes))
+    }
+
+    /// replaces the value of the parameter, the values are clamped to the min and max of the parameter
+    pub fn set(&mut self, name: &str, values: &[f32]) -> Result<(), ParameterError> {
+        let parameter = self
+            .layout
+            .get(name)
+            .ok_or_else(|| ParameterError::UnknownParameter(name.to_string()))?;
+        if values.len() != parameter.ty.components() {
+            return Err(ParameterError::WrongComponentCount {
+                name: name.to_string(),
+                expected: parameter.ty.components(),
+                actual: values.len(),
+            });
+        }
+
+        let values: Vec<f32> = values.iter().map(|value| parameter.clamp(*value)).collect();
+        if self.get(name).as_deref() != Some(values.as_slice()) {
+            let parameter = parameter.clone();
+            write_values(&mut self.data, &parameter, &values);
+            self.changed = true;
+        }
+        Ok(())
+    }
+
+    /// writes the values to the GPU if they changed since the last upload
+    pub fn upload(&mut self, queue: &wgpu::Queue) {
+        if self.changed {
+            queue.write_buffer(&self.buffer, 0, &self.data);
+            self.changed = false;
+        }
+    }
+
+    pub fn bind_group(&self) -> &wgpu::BindGroup {
+        &self.bind_group
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn invalid_annotation(source: &str) -> (usize, String) {
+        match ParameterLayout::parse(source) {
+            Err(ParameterError::InvalidAnnotation { line, message }) => (line, message),
+            result => panic!("expected an invalid annotation but got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn annotations_are_parsed() {
+        let layout = ParameterLayout::parse(
+            "// @param strength f32 min=0 max=1 default=0.5\nfn main() {}\n  //@param center vec2<f32> default=0.25,0.75\n// strength",
+        )
+        .expect("the annotations are valid");
+        assert_eq!(
+            layout.parameters,
+            [
+                ParameterInfo {
+                    name: "strength".to_string(),
+                    ty: ParameterType::F32,
+                    min: Some(0.),
+                    max: Some(1.),
+                    default: vec![0.5],
+                    offset: 0,
+                },
+                ParameterInfo {
+                    name: "center".to_string(),
+                    ty: ParameterType::Vec2,
+                    min: None,
+                    max: None,
+                    default: vec![0.25, 0.75],
+                    offset: 8,
+                },
+            ]
+        );
+        assert_eq!(layout.size, 16);
+    }
+
+    #[test]
+    fn missing_defaults_are_zero() {
+        let layout = ParameterLayout::parse("// @param color vec3f").expect("the annotation is valid");
+        assert_eq!(layout.parameters[0].default, [0., 0., 0.]);
+    }
+
+    #[test]
+    fn longer_words_are_no_annotations() {
+        let layout = ParameterLayout::parse("// @parameters of the blur\n//@params\n// @param strength f32")
+            .expect("only the last line is an annotation");
+        assert_eq!(layout.parameters.len(), 1);
+        assert_eq!(layout.parameters[0].name, "strength");
+        assert_eq!(invalid_annotation("// @param"), (1, "missing name".to_string()));
+    }
+
+    #[test]
+    fn missing_defaults_are_clamped_into_the_range() {
+        let layout = ParameterLayout::parse("// @param a f32 min=1\n// @param b vec2f min=-2 max=-1\n// @param c f32 min=-1 max=1")
+            .expect("the annotations have no default");
+        let defaults: Vec<_> = layout.parameters.iter().map(|parameter| parameter.default.clone()).collect();
+        assert_eq!(defaults, [vec![1.], vec![-1., -1.], vec![0.]]);
+    }
+
+    #[test]
+    fn offsets_follow_the_uniform_alignment() {
+        let layout = ParameterLayout::parse(
+            "// @param a f32\n// @param b vec3f\n// @param c f32\n// @param d vec2f\n// @param e vec4f\n// @param f f32",
+        )
+        .expect("the annotations are valid");
+        let offsets: Vec<_> = layout.parameters.iter().map(|parameter| parameter.offset).collect();
+        assert_eq!(offsets, [0, 16, 28, 32, 48, 64]);
+        assert_eq!(layout.size, 80);
+    }
+
+    #[test]
+    fn the_declaration_is_padded_to_16_bytes() {
+        let layout = ParameterLayout::parse("// @param a f32\n// @param b f32").expect("the annotations are valid");
+        assert_eq!(layout.size, 16);
+        assert_eq!(
+            layout.wgsl_declarations(),
+            "struct Parameters {\n    // WebGL needs 16 byte alignment\n    a: f32,\n    b: f32,\n    _padding0: f32,\n    _padding1: f32,\n}\n\
+             @group(2) @binding(0)\nvar<uniform> parameters: Parameters;\n"
+        );
+
+        let layout = ParameterLayout::parse("// @param a vec4f").expect("the annotation is valid");
+        assert!(!layout.wgsl_declarations().contains("_padding"));
+    }
+
+    #[test]
+    fn an_empty_layout_has_no_size() {
+        let layout = ParameterLayout::parse("fn main() {}").expect("there are no annotations");
+        assert!(layout.is_empty());
+        assert_eq!(layout.size, 0);
+    }
+
+    #[test]
+    fn invalid_annotations_are_rejected_with_their_line() {
+        for (annotation, message) in [
+            ("// @param", "missing name"),
+            ("// @param 1a f32", "'1a' is not a valid identifier"),
+            ("// @param a", "missing type of 'a'"),
+            ("// @param a f64", "unsupported type 'f64'"),
+            ("// @param a f32 min", "expected key=value but got 'min'"),
+            ("// @param a f32 min=x", "invalid number 'x' for min"),
+            ("// @param a f32 step=1", "unknown option 'step'"),
+            ("// @param a vec2f default=1", "default of 'a' needs 2 values"),
+            ("// @param x f32", "'x' is declared twice"),
+        ] {
+            assert_eq!(
+                invalid_annotation(&format!("// @param x f32\n{annotation}")),
+                (2, message.to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn min_greater_than_max_is_rejected() {
+        let (line, message) = invalid_annotation("// @param a f32 max=0 min=1");
+        assert_eq!((line, message.as_str()), (1, "min 1 of 'a' is greater than max 0"));
+    }
+
+    #[test]
+    fn defaults_outside_of_the_range_are_rejected() {
+        for annotation in ["// @param a f32 min=0 max=1 default=2", "// @param a vec2f default=0,-1 min=0"] {
+            let (line, message) = invalid_annotation(annotation);
+            assert_eq!(line, 1);
+            assert!(message.contains("outside of min and max"), "{}", message);
+        }
+        ParameterLayout::parse("// @param a f32 default=1 min=1 max=1").expect("the default is inside the range");
+    }
+}
@@ -0,0 +1,354 @@
+//! the passes of multi pass effects, declared by annotations in the WGSL file of the effect:
+//!
+//! ```wgsl
+//! // @target half scale=0.5
+//! // @pass blur_x output=half
+//! // @pass blur_y
+//! ```
+//!
+//! every `@target` is a render target that the effect owns, its size is the size of the chain scaled by `scale`.
+//! Every `@pass` draws the fullscreen rectangle with the fragment entry point of the same name. It reads the output of the
+//! previous pass or its `input=<target>` and writes to its `output=<target>`, the last pass writes to the destination of the effect.
+//! `input=source` reads the input of the effect, which every pass can also read as `t_source` (see `shaders/multipass.wgsl`).
+//! Passes with `blend=add` add their result to the content of their output instead of replacing it.
+
+use crate::effect_parameters::{is_identifier, strip_keyword};
+
+const TARGET_ANNOTATION: &str = "@target";
+const PASS_ANNOTATION: &str = "@pass";
+/// the input of the effect, it can't be used as output
+const SOURCE: &str = "source";
+
+/// a render target owned by a multi pass effect
+#[derive(Debug, Clone, PartialEq)]
+pub struct TargetInfo {
+    pub name: String,
+    /// size relative to the render targets of the chain
+    pub scale: f32,
+}
+
+impl TargetInfo {
+    /// the size of the target for render targets of the given size, at least one pixel in each direction
+    pub fn size(&self, size: wgpu::Extent3d) -> wgpu::Extent3d {
+        let scale = |length: u32| ((length as f32 * self.scale).round() as u32).max(1);
+        wgpu::Extent3d {
+            width: scale(size.width),
+            height: scale(size.height),
+            depth_or_array_layers: 1,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PassInput {
+    /// the input of the effect
+    Source,
+    /// index of a target in `PassLayout::targets`
+    Target(usize),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PassInfo {
+    /// name of the fragment entry point
+    pub entry_point: String,
+    pub input: PassInput,
+    /// index of a target in `PassLayout::targets`, `None` for the last pass that writes to the destination
+    pub output: Option<usize>,
+    /// adds the result to the output instead of replacing it
+    pub additive: bool,
+}
+
+#[derive(Debug)]
+pub enum PassError {
+    InvalidAnnotation {
+        line: usize,
+        message: String,
+    },
+    /// the effect is a multi pass effect but does not declare a pass
+    NoPasses,
+}
+
+impl std::fmt::Display for PassError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PassError::InvalidAnnotation { line, message } => write!(f, "line {line}: invalid annotation, {message}"),
+            PassError::NoPasses => write!(f, "multi pass effects need at least one {PASS_ANNOTATION} annotation"),
+        }
+    }
+}
+
+impl std::error::Error for PassError {}
+
+/// the targets and passes declared by the `@target` and `@pass` annotations of a shader
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PassLayout {
+    pub targets: Vec<TargetInfo>,
+    /// in the order in which they are rendered
+    pub passes: Vec<PassInfo>,
+}
+
+impl PassLayout {
+    /// collects all `// @target <name> [scale=<factor>]` and `// @pass <entry point> [input=<target>] [output=<target>] [blend=add]`
+    /// annotations. Targets have to be declared before the passes that use them.
+    pub fn parse(source: &str) -> Result<Self, PassError> {
+        let mut layout = Self::default();
+        // line of every pass for errors that are only found after all passes are known
+        let mut pass_lines = Vec::new();
+
+        for (line_index, line) in source.lines().enumerate() {
+            let Some(annotation) = line.trim().strip_prefix("//").map(str::trim) else {
+                continue;
+            };
+            let error = |message: String| PassError::InvalidAnnotation {
+                line: line_index + 1,
+                message,
+            };
+
+            if let Some(annotation) = strip_keyword(annotation, TARGET_ANNOTATION) {
+                let mut words = annotation.split_whitespace();
+                let name = words.next().ok_or_else(|| error("missing target name".to_string()))?;
+                if !is_identifier(name) || name == SOURCE {
+                    return Err(error(format!("'{name}' is not a valid target name")));
+                }
+                if layout.target_index(name).is_some() {
+                    return Err(error(format!("target '{name}' is declared twice")));
+                }
+
+                let mut target = TargetInfo {
+                    name: name.to_string(),
+                    scale: 1.,
+                };
+                for option in words {
+                    match split_option(option).map_err(&error)? {
+                        ("scale", value) => {
+                            target.scale = value
+                                .parse::<f32>()
+                                .ok()
+                                .filter(|scale| *scale > 0. && *scale <= 1.)
+                                .ok_or_else(|| error(format!("scale of '{name}' has to be a number from 0 to 1")))?;
+                        }
+                        (key, _) => return Err(error(format!("unknown option '{key}'"))),
+                    }
+                }
+                layout.targets.push(target);
+            } else if let Some(annotation) = strip_keyword(annotation, PASS_ANNOTATION) {
+                let mut words = annotation.split_whitespace();
+                let entry_point = words.next().ok_or_else(|| error("missing entry point".to_string()))?;
+                if !is_identifier(entry_point) {
+                    return Err(error(format!("'{entry_point}' is not a valid entry point")));
+                }
+
+                let previous_output = layout.passes.last().and_then(|pass| pass.output);
+                let mut pass = PassInfo {
+                    entry_point: entry_point.to_string(),
+                    input: previous_output.map_or(PassInput::Source, PassInput::Target),
+                    output: None,
+                    additive: false,
+                };
+                for option in words {
+                    let target_index = |name: &str| layout.target_index(name).ok_or_else(|| error(format!("unknown target '{name}'")));
+                    match split_option(option).map_err(&error)? {
+                        ("input", SOURCE) => pass.input = PassInput::Source,
+                        ("input", name) => pass.input = PassInput::Target(target_index(name)?),
+                        ("output", name) => pass.output = Some(target_index(name)?),
+                        ("blend", "add") => pass.additive = true,
+                        ("blend", value) => return Err(error(format!("unknown blend mode '{value}', expected add"))),
+                        (key, _) => return Err(error(format!("unknown option '{key}'"))),
+                    }
+                }
+                if pass.output.is_some_and(|output| pass.input == PassInput::Target(output)) {
+                    return Err(error(format!("pass '{entry_point}' can't read from its output")));
+                }
+                layout.passes.push(pass);
+                pass_lines.push(line_index + 1);
+            }
+        }
+
+        // every pass except the last one needs an output, otherwise it would be overwritten by the following passes
+        let last = layout.passes.len().saturating_sub(1);
+        for (index, (pass, line)) in layout.passes.iter().zip(pass_lines).enumerate() {
+            let message = match (index == last, pass.output) {
+                (true, Some(_)) => "the last pass writes to the destination and can't have an output",
+                (false, None) => "only the last pass writes to the destination, the other passes need an output",
+                _ => continue,
+            };
+            return Err(PassError::InvalidAnnotation {
+                line,
+                message: message.to_string(),
+            });
+        }
+
+        Ok(layout)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.passes.is_empty()
+    }
+
+    fn target_index(&self, name: &str) -> Option<usize> {
+        self.targets.iter().position(|target| target.name == name)
+    }
+}
+
+fn split_option(option: &str) -> Result<(&str, &str), String> {
+    option
+        .split_once('=')
+        .ok_or_else(|| format!("expected key=value but got '{option}'"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::effect_registry::EffectKind;
+
+    fn invalid_annotation(source: &str) -> (usize, String) {
+        match PassLayout::parse(source) {
+            Err(PassError::InvalidAnnotation { line, message }) => (line, message),
+            result => panic!("expected an invalid annotation but got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn passes_read_the_output_of_the_previous_pass() {
+        let layout = PassLayout::parse(
+            "// @target half scale=0.5\n// @target bright\n// @pass threshold output=bright\nfn threshold() {}\n\
+             // @pass blur output=half\n// @pass combine input=source blend=add",
+        )
+        .expect("the annotations are valid");
+        assert_eq!(
+            layout.targets,
+            [
+                TargetInfo {
+                    name: "half".to_string(),
+                    scale: 0.5,
+                },
+                TargetInfo {
+                    name: "bright".to_string(),
+                    scale: 1.,
+                },
+            ]
+        );
+        let passes: Vec<_> = layout
+            .passes
+            .iter()
+            .map(|pass| (pass.entry_point.as_str(), pass.input, pass.output, pass.additive))
+            .collect();
+        assert_eq!(
+            passes,
+            [
+                ("threshold", PassInput::Source, Some(1), false),
+                ("blur", PassInput::Target(1), Some(0), false),
+                ("combine", PassInput::Source, None, true),
+            ]
+        );
+    }
+
+    #[test]
+    fn target_sizes_are_scaled_and_at_least_one_pixel() {
+        let target = TargetInfo {
+            name: "quarter".to_string(),
+            scale: 0.25,
+        };
+        let size = |width, height| wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+        assert_eq!(target.size(size(400, 110)), size(100, 28));
+        assert_eq!(target.size(size(2, 1)), size(1, 1));
+    }
+
+    #[test]
+    fn undeclared_targets_are_rejected() {
+        for annotation in ["// @pass blur input=half", "// @pass blur output=half"] {
+            assert_eq!(
+                invalid_annotation(&format!("// @target full\n{annotation}")),
+                (2, "unknown target 'half'".to_string())
+            );
+        }
+        // targets have to be declared before they are used
+        let (line, message) = invalid_annotation("// @pass blur output=half\n// @pass combine\n// @target half");
+        assert_eq!((line, message.as_str()), (1, "unknown target 'half'"));
+    }
+
+    #[test]
+    fn passes_cant_write_to_their_input() {
+        let (line, message) = invalid_annotation("// @target half\n// @pass blur input=half output=half");
+        assert_eq!((line, message.as_str()), (2, "pass 'blur' can't read from its output"));
+
+        // the input of the second pass is the output of the first one
+        let (line, message) = invalid_annotation("// @target half\n// @pass a output=half\n// @pass b output=half\n// @pass c");
+        assert_eq!((line, message.as_str()), (3, "pass 'b' can't read from its output"));
+    }
+
+    #[test]
+    fn only_the_last_pass_writes_to_the_destination() {
+        let (line, message) = invalid_annotation("// @target half\n// @pass a output=half\n// @pass b input=source output=half");
+        assert_eq!(
+            (line, message.as_str()),
+            (3, "the last pass writes to the destination and can't have an output")
+        );
+
+        let (line, message) = invalid_annotation("// @pass a\n// @pass b");
+        assert_eq!(
+            (line, message.as_str()),
+            (1, "only the last pass writes to the destination, the other passes need an output")
+        );
+    }
+
+    #[test]
+    fn invalid_targets_are_rejected_with_their_line() {
+        for (annotation, message) in [
+            ("// @target", "missing target name"),
+            ("// @target source", "'source' is not a valid target name"),
+            ("// @target 2x", "'2x' is not a valid target name"),
+            ("// @target full", "target 'full' is declared twice"),
+            ("// @target half scale", "expected key=value but got 'scale'"),
+            ("// @target half scale=0", "scale of 'half' has to be a number from 0 to 1"),
+            ("// @target half scale=2", "scale of 'half' has to be a number from 0 to 1"),
+            ("// @target half format=rgba8", "unknown option 'format'"),
+        ] {
+            assert_eq!(
+                invalid_annotation(&format!("// @target full\n{annotation}")),
+                (2, message.to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_passes_are_rejected_with_their_line() {
+        for (annotation, message) in [
+            ("// @pass", "missing entry point"),
+            ("// @pass blur-x", "'blur-x' is not a valid entry point"),
+            ("// @pass blur output", "expected key=value but got 'output'"),
+            ("// @pass blur blend=multiply", "unknown blend mode 'multiply', expected add"),
+            ("// @pass blur scale=0.5", "unknown option 'scale'"),
+        ] {
+            assert_eq!(
+                invalid_annotation(&format!("// @target full\n{annotation}")),
+                (2, message.to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn longer_words_are_no_annotations() {
+        let layout = PassLayout::parse("// @passthrough for WebGL\n// @targets of the bloom\n// @target half\n// @pass blur")
+            .expect("only the last two lines are annotations");
+        assert_eq!(layout.targets.len(), 1);
+        assert_eq!(layout.passes.len(), 1);
+        assert_eq!(invalid_annotation("// @pass"), (1, "missing entry point".to_string()));
+    }
+
+    #[test]
+    fn multi_pass_effects_need_a_pass() {
+        assert!(matches!(
+            EffectKind::MultiPass.parse_passes("// @target half scale=0.5"),
+            Err(PassError::NoPasses)
+        ));
+        let passes = EffectKind::Fragment
+            .parse_passes("// @pass a\n// @pass b")
+            .expect("other effects ignore the annotations");
+        assert!(passes.is_empty());
+    }
+}
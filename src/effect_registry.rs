@@ -0,0 +1,360 @@
+use crate::effect_parameters::{ParameterError, ParameterLayout};
+use crate::effect_passes::{PassError, PassLayout};
+use crate::lut::{self, CubeLut, LutTexture};
+use crate::post_processing::PostProcessing;
+use crate::shader_preprocessor;
+use std::borrow::Cow;
+
+/// how the shader of an effect is completed before it is compiled
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum EffectKind {
+    /// a fragment shader that includes `prelude.wgsl` and declares the `fragment` entry point itself
+    Fragment,
+    /// Shadertoy code translated to WGSL that only declares `mainImage`, it is wrapped by `shadertoy.wgsl`
+    Shadertoy,
+    /// a compute shader that declares the `compute` entry point and includes the declarations of `compute.wgsl`
+    Compute,
+    /// a fragment shader with several entry points that are rendered one after another as declared by its `@pass` annotations,
+    /// it includes the declarations of `multipass.wgsl`
+    MultiPass,
+    /// a fragment shader that grades colors with `lut_color` from `lut.wgsl`, the lookup table can be replaced at runtime
+    Lut,
+}
+
+impl EffectKind {
+    /// effect files named `shadertoy_*.wgsl` contain Shadertoy code, `compute_*.wgsl` compute shaders
+    /// `multipass_*.wgsl` multi pass effects and `lut_*.wgsl` effects with a lookup table
+    pub fn from_file_name(shader_file: &str) -> Self {
+        if shader_file.starts_with("shadertoy_") {
+            EffectKind::Shadertoy
+        } else if shader_file.starts_with("compute_") {
+            EffectKind::Compute
+        } else if shader_file.starts_with("multipass_") {
+            EffectKind::MultiPass
+        } else if shader_file.starts_with("lut_") {
+            EffectKind::Lut
+        } else {
+            EffectKind::Fragment
+        }
+    }
+
+    /// appends the declarations the effect expects to the shader source.
+    /// Module scope declarations can be used before they are declared, so appending keeps the line numbers of errors.
+    pub fn complete_source(self, shader_source: &str, parameters: &ParameterLayout) -> String {
+        let mut source = shader_source.to_string();
+        match self {
+            EffectKind::Fragment => {}
+            EffectKind::Shadertoy => source.push_str("\n#include \"shadertoy.wgsl\"\n"),
+            EffectKind::Compute => source.push_str("\n#include \"compute.wgsl\"\n"),
+            EffectKind::MultiPass => source.push_str("\n#include \"multipass.wgsl\"\n"),
+            EffectKind::Lut => source.push_str("\n#include \"lut.wgsl\"\n"),
+        }
+        if !parameters.is_empty() {
+            source.push('\n');
+            source.push_str(&parameters.wgsl_declarations());
+        }
+        source
+    }
+
+    /// the `@target` and `@pass` annotations of multi pass effects, the passes of other effects are empty
+    pub fn parse_passes(self, shader_source: &str) -> Result<PassLayout, PassError> {
+        if self != EffectKind::MultiPass {
+            return Ok(PassLayout::default());
+        }
+        let passes = PassLayout::parse(shader_source)?;
+        match passes.is_empty() {
+            true => Err(PassError::NoPasses),
+            false => Ok(passes),
+        }
+    }
+}
+
+/// the annotations of an effect file are invalid
+#[derive(Debug)]
+pub enum EffectError {
+    Parameter(ParameterError),
+    Pass(PassError),
+}
+
+impl std::fmt::Display for EffectError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EffectError::Parameter(error) => write!(f, "{error}"),
+            EffectError::Pass(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl std::error::Error for EffectError {}
+
+impl From<ParameterError> for EffectError {
+    fn from(error: ParameterError) -> Self {
+        EffectError::Parameter(error)
+    }
+}
+
+impl From<PassError> for EffectError {
+    fn from(error: PassError) -> Self {
+        EffectError::Pass(error)
+    }
+}
+
+/// everything needed to create a post processing effect
+pub struct EffectDescriptor {
+    pub name: String,
+    pub kind: EffectKind,
+    /// file name of the shader relative to `src/shaders`, used for include errors and hot reloading
+    pub shader_file: String,
+    pub shader_source: Cow<'static, str>,
+    /// parameters declared by the `// @param` annotations of the shader file, see `effect_parameters`
+    pub parameters: ParameterLayout,
+    /// passes declared by the `// @pass` annotations of multi pass effects, see `effect_passes`
+    pub passes: PassLayout,
+}
+
+impl EffectDescriptor {
+    /// the kind of the effect follows from the file name, see `EffectKind::from_file_name`.
+    /// Fails if the `// @param`, `// @target` or `// @pass` annotations of the shader source are invalid.
+    pub fn new(
+        name: impl Into<String>,
+        shader_file: impl Into<String>,
+        shader_source: impl Into<Cow<'static, str>>,
+    ) -> Result<Self, EffectError> {
+        let shader_file = shader_file.into();
+        let shader_source = shader_source.into();
+        let kind = EffectKind::from_file_name(&shader_file);
+        Ok(Self {
+            name: name.into(),
+            kind,
+            shader_file,
+            parameters: ParameterLayout::parse(&shader_source)?,
+            passes: kind.parse_passes(&shader_source)?,
+            shader_source,
+        })
+    }
+
+    /// `size` is the size of the render targets of the chain, see `PostProcessing::resize`.
+    /// Effects of the `Lut` kind start with the table of `lut::DEFAULT_LUT`, which is uploaded with the queue.
+    pub fn create(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        format: wgpu::TextureFormat,
+        size: wgpu::Extent3d,
+        globals_bind_group_layout: &wgpu::BindGroupLayout,
+    ) -> PostProcessing {
+        let parameters = (!self.parameters.is_empty()).then(|| self.parameters.clone());
+
+        if self.kind == EffectKind::Compute && !PostProcessing::is_compute_supported(device) {
+            // the effect keeps its parameters, so changing them works on every backend
+            log::warn!(
+                "compute shaders are not supported, the effect {} passes its input through unchanged",
+                self.name
+            );
+            let shader = shader_preprocessor::create_embedded_shader_module(device, "copy.wgsl");
+            return match parameters {
+                Some(parameters) => PostProcessing::with_parameters(device, format, globals_bind_group_layout, shader, parameters),
+                None => PostProcessing::new(device, format, globals_bind_group_layout, shader),
+            };
+        }
+
+        let source = self.kind.complete_source(&self.shader_source, &self.parameters);
+        let shader = shader_preprocessor::create_shader_module(device, &self.shader_file, &source);
+        match (self.kind, parameters) {
+            (EffectKind::Compute, parameters) => {
+                PostProcessing::compute(device, format, size, globals_bind_group_layout, shader, parameters)
+            }
+            (EffectKind::MultiPass, parameters) => PostProcessing::multi_pass(
+                device,
+                format,
+                size,
+                globals_bind_group_layout,
+                shader,
+                parameters,
+                self.passes.clone(),
+            ),
+            (EffectKind::Lut, parameters) => {
+                let default_lut = CubeLut::parse(lut::DEFAULT_LUT).unwrap_or_else(|error| panic!("{} of the default LUT", error));
+                let lut = LutTexture::new(device, queue, &default_lut, "default lut").expect("the default LUT fits into every device");
+                PostProcessing::with_lut(device, format, globals_bind_group_layout, shader, parameters, lut)
+            }
+            (_, Some(parameters)) => PostProcessing::with_parameters(device, format, globals_bind_group_layout, shader, parameters),
+            (_, None) => PostProcessing::new(device, format, globals_bind_group_layout, shader),
+        }
+    }
+}
+
+/// maps stable effect names to their descriptors
+///
+/// effects keep the order in which they were registered, the position of an effect is its effect index.
+/// Effects that are implemented in Rust are registered by name only, they have no descriptor.
+#[derive(Default)]
+pub struct EffectRegistry {
+    effects: Vec<RegisteredEffect>,
+}
+
+struct RegisteredEffect {
+    name: String,
+    descriptor: Option<EffectDescriptor>,
+}
+
+impl EffectRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// a registry with all effects that come with this example: invert_color, wave_distortion, droplet, fxaa, vignette,
+    /// chromatic_aberration, film_grain, lens_distortion, crt, edge_sobel, edge_prewitt, edge_laplacian, toon, pixelate,
+    /// box_blur, bloom, color_grading, gaussian_blur and kawase_blur
+    pub fn built_in() -> Self {
+        let mut registry = Self::new();
+        let built_in_effects = [
+            (
+                "invert_color",
+                "post_processing_invert_color.wgsl",
+                include_str!("shaders/post_processing_invert_color.wgsl"),
+            ),
+            (
+                "wave_distortion",
+                "post_processing_wave_distortion.wgsl",
+                include_str!("shaders/post_processing_wave_distortion.wgsl"),
+            ),
+            (
+                "droplet",
+                "post_processing_droplet.wgsl",
+                include_str!("shaders/post_processing_droplet.wgsl"),
+            ),
+            (
+                "fxaa",
+                "post_processing_fxaa.wgsl",
+                include_str!("shaders/post_processing_fxaa.wgsl"),
+            ),
+            (
+                "vignette",
+                "post_processing_vignette.wgsl",
+                include_str!("shaders/post_processing_vignette.wgsl"),
+            ),
+            (
+                "chromatic_aberration",
+                "post_processing_chromatic_aberration.wgsl",
+                include_str!("shaders/post_processing_chromatic_aberration.wgsl"),
+            ),
+            (
+                "film_grain",
+                "post_processing_film_grain.wgsl",
+                include_str!("shaders/post_processing_film_grain.wgsl"),
+            ),
+            (
+                "lens_distortion",
+                "post_processing_lens_distortion.wgsl",
+                include_str!("shaders/post_processing_lens_distortion.wgsl"),
+            ),
+            ("crt", "post_processing_crt.wgsl", include_str!("shaders/post_processing_crt.wgsl")),
+            (
+                "edge_sobel",
+                "post_processing_edge_sobel.wgsl",
+                include_str!("shaders/post_processing_edge_sobel.wgsl"),
+            ),
+            (
+                "edge_prewitt",
+                "post_processing_edge_prewitt.wgsl",
+                include_str!("shaders/post_processing_edge_prewitt.wgsl"),
+            ),
+            (
+                "edge_laplacian",
+                "post_processing_edge_laplacian.wgsl",
+                include_str!("shaders/post_processing_edge_laplacian.wgsl"),
+            ),
+            (
+                "toon",
+                "post_processing_toon.wgsl",
+                include_str!("shaders/post_processing_toon.wgsl"),
+            ),
+            (
+                "pixelate",
+                "shadertoy_pixelate.wgsl",
+                include_str!("shaders/shadertoy_pixelate.wgsl"),
+            ),
+            ("box_blur", "compute_box_blur.wgsl", include_str!("shaders/compute_box_blur.wgsl")),
+            ("bloom", "multipass_bloom.wgsl", include_str!("shaders/multipass_bloom.wgsl")),
+            (
+                "color_grading",
+                "lut_color_grading.wgsl",
+                include_str!("shaders/lut_color_grading.wgsl"),
+            ),
+            (
+                "gaussian_blur",
+                "multipass_gaussian_blur.wgsl",
+                include_str!("shaders/multipass_gaussian_blur.wgsl"),
+            ),
+            (
+                "kawase_blur",
+                "multipass_kawase_blur.wgsl",
+                include_str!("shaders/multipass_kawase_blur.wgsl"),
+            ),
+        ];
+        for (name, shader_file, shader_source) in built_in_effects {
+            let descriptor = EffectDescriptor::new(name, shader_file, shader_source)
+                .unwrap_or_else(|error| panic!("{} of the built in effect {}", error, name));
+            registry.register(descriptor);
+        }
+        registry
+    }
+
+    /// adds the effect and returns its index. An effect with the same name is replaced and keeps its index.
+    pub fn register(&mut self, descriptor: EffectDescriptor) -> usize {
+        let name = descriptor.name.clone();
+        self.insert(RegisteredEffect {
+            name,
+            descriptor: Some(descriptor),
+        })
+    }
+
+    /// adds an effect that is implemented in Rust and returns its index, see `register`
+    pub fn register_name(&mut self, name: impl Into<String>) -> usize {
+        self.insert(RegisteredEffect {
+            name: name.into(),
+            descriptor: None,
+        })
+    }
+
+    fn insert(&mut self, effect: RegisteredEffect) -> usize {
+        match self.index_of(&effect.name) {
+            Some(index) => {
+                self.effects[index] = effect;
+                index
+            }
+            None => {
+                self.effects.push(effect);
+                self.effects.len() - 1
+            }
+        }
+    }
+
+    /// the index of an effect is its position in the registry, it doesn't change when the effect is replaced
+    pub fn index_of(&self, name: &str) -> Option<usize> {
+        self.effects.iter().position(|effect| effect.name == name)
+    }
+
+    /// name of the effect at the given index
+    pub fn name(&self, index: usize) -> Option<&str> {
+        self.effects.get(index).map(|effect| effect.name.as_str())
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.effects.iter().map(|effect| effect.name.as_str())
+    }
+
+    /// the descriptor of the effect at the given index, `None` for effects that are implemented in Rust
+    pub fn descriptor(&self, index: usize) -> Option<&EffectDescriptor> {
+        self.effects.get(index)?.descriptor.as_ref()
+    }
+
+    /// the effects with a descriptor and their index
+    pub fn descriptors(&self) -> impl Iterator<Item = (usize, &EffectDescriptor)> {
+        self.effects
+            .iter()
+            .enumerate()
+            .filter_map(|(index, effect)| Some((index, effect.descriptor.as_ref()?)))
+    }
+}
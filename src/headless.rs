@@ -0,0 +1,373 @@
+use crate::effect::Effect;
+use crate::effect_parameters::{EffectParameters, ParameterError};
+use crate::effect_registry::{EffectDescriptor, EffectRegistry};
+use crate::lut::{CubeLut, LutError, LutTexture};
+use crate::post_processor::{PostProcessor, UnknownEffect};
+use crate::render_target::RenderTarget;
+use crate::scene::{Scene, TextureScene};
+use crate::shader_globals::Globals;
+use crate::shader_preprocessor;
+use std::path::Path;
+
+/// the format of the rendered images, matches the 8 bit RGBA layout of `image::RgbaImage`
+pub const TEXTURE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+#[derive(Debug)]
+pub enum HeadlessError {
+    /// no adapter matching the requested options was found
+    NoAdapter,
+    RequestDevice(wgpu::RequestDeviceError),
+    /// reading the rendered frame back from the GPU failed
+    BufferMap(wgpu::BufferAsyncError),
+    /// an effect file could not be read or does not compile
+    LoadEffect(String),
+    Parameter(ParameterError),
+    Lut(LutError),
+    UnknownEffect(UnknownEffect),
+    /// the frame size is 0 or larger than the textures of the device can be
+    InvalidSize {
+        width: u32,
+        height: u32,
+    },
+}
+
+impl std::fmt::Display for HeadlessError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HeadlessError::NoAdapter => write!(f, "no suitable graphics adapter found"),
+            HeadlessError::RequestDevice(error) => write!(f, "could not create a device: {error}"),
+            HeadlessError::BufferMap(error) => {
+                write!(f, "could not read the rendered frame: {error}")
+            }
+            HeadlessError::LoadEffect(error) => write!(f, "could not load effect {error}"),
+            HeadlessError::Parameter(error) => write!(f, "{error}"),
+            HeadlessError::Lut(error) => write!(f, "{error}"),
+            HeadlessError::UnknownEffect(error) => write!(f, "{error}"),
+            HeadlessError::InvalidSize { width, height } => write!(f, "can't render frames of {width}x{height} pixels"),
+        }
+    }
+}
+
+impl std::error::Error for HeadlessError {}
+
+impl From<UnknownEffect> for HeadlessError {
+    fn from(error: UnknownEffect) -> Self {
+        HeadlessError::UnknownEffect(error)
+    }
+}
+
+/// renders the scene and post processing effects into an owned texture instead of a window surface
+///
+/// the processed frame is copied back into CPU memory, which makes it possible to run the effects
+/// without a display, e.g. on CI machines with a software adapter.
+pub struct HeadlessRenderer {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    size: wgpu::Extent3d,
+    scene: Box<dyn Scene>,
+    scene_target: RenderTarget,
+    post_processor: PostProcessor,
+    output_texture: wgpu::Texture,
+}
+
+impl HeadlessRenderer {
+    /// creates a renderer for frames of the given size, which is at least 1x1 pixels.
+    /// Set `force_fallback_adapter` to use the software adapter of the platform.
+    pub async fn new(width: u32, height: u32, force_fallback_adapter: bool) -> Result<Self, HeadlessError> {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+            backends: wgpu::Backends::all(),
+            ..Default::default()
+        });
+
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::default(),
+                compatible_surface: None,
+                force_fallback_adapter,
+            })
+            .await
+            .ok_or(HeadlessError::NoAdapter)?;
+
+        let (device, queue) = adapter
+            .request_device(
+                &wgpu::DeviceDescriptor {
+                    required_features: wgpu::Features::empty(),
+                    // software adapters don't necessarily support the default limits
+                    required_limits: wgpu::Limits::downlevel_defaults().using_resolution(adapter.limits()),
+                    label: None,
+                    memory_hints: Default::default(),
+                },
+                None, // Trace path
+            )
+            .await
+            .map_err(HeadlessError::RequestDevice)?;
+
+        let size = frame_size(&device, width, height)?;
+        let scene_target = RenderTarget::new(&device, size, TEXTURE_FORMAT, "initial render pass canvas");
+        let post_processor = PostProcessor::new(&device, TEXTURE_FORMAT, scene_target.view(), size);
+        let scene = Box::new(TextureScene::new(
+            &device,
+            &queue,
+            TEXTURE_FORMAT,
+            post_processor.globals_bind_group_layout(),
+        ));
+        let output_texture = create_output_texture(&device, size);
+
+        Ok(Self {
+            device,
+            queue,
+            size,
+            scene,
+            scene_target,
+            post_processor,
+            output_texture,
+        })
+    }
+
+    /// the device, queue and globals layout are needed to create scenes for `set_scene`
+    pub fn device(&self) -> &wgpu::Device {
+        &self.device
+    }
+
+    pub fn queue(&self) -> &wgpu::Queue {
+        &self.queue
+    }
+
+    pub fn globals_bind_group_layout(&self) -> &wgpu::BindGroupLayout {
+        self.post_processor.globals_bind_group_layout()
+    }
+
+    /// number of available post processing effects, valid effect indices are `0..effect_count()`
+    pub fn effect_count(&self) -> usize {
+        self.post_processor.effect_count()
+    }
+
+    /// names of the available post processing effects, the position of a name is its effect index
+    pub fn effect_names(&self) -> impl Iterator<Item = &str> {
+        self.post_processor.effect_names()
+    }
+
+    pub fn effect_index(&self, name: &str) -> Option<usize> {
+        self.post_processor.effect_index(name)
+    }
+
+    /// the registered effects including the loaded effect files, e.g. to parse presets that use them
+    pub fn effect_registry(&self) -> &EffectRegistry {
+        self.post_processor.effect_registry()
+    }
+
+    /// registers the effect of a WGSL file that declares its parameters with `// @param` annotations, see `effect_parameters`.
+    /// Files named `shadertoy_*.wgsl` contain Shadertoy code, see `shaders/shadertoy.wgsl`, and files named `multipass_*.wgsl`
+    /// multi pass effects, see `effect_passes`, and files named `lut_*.wgsl` effects with a lookup table.
+    /// The effect is named after the file without the `post_processing_`, `shadertoy_`, `compute_`, `multipass_` or `lut_` prefix
+    /// and replaces an effect with the same name.
+    /// Only the embedded shaders like `prelude.wgsl` can be included.
+    pub fn load_effect_file(&mut self, path: &Path) -> Result<usize, HeadlessError> {
+        let load_error = |error: &dyn std::fmt::Display| HeadlessError::LoadEffect(format!("{}: {error}", path.display()));
+
+        let source = std::fs::read_to_string(path).map_err(|error| load_error(&error))?;
+        let shader_file = path.file_name().unwrap_or(path.as_os_str()).to_string_lossy().into_owned();
+        let file_stem = path.file_stem().unwrap_or(path.as_os_str()).to_string_lossy();
+        let name = ["post_processing_", "shadertoy_", "compute_", "multipass_", "lut_"]
+            .iter()
+            .find_map(|prefix| file_stem.strip_prefix(prefix))
+            .unwrap_or(&file_stem);
+        let descriptor = EffectDescriptor::new(name, shader_file.as_str(), source).map_err(|error| load_error(&error))?;
+
+        // a missing include would panic in `EffectDescriptor::create`
+        let complete_source = descriptor.kind.complete_source(&descriptor.shader_source, &descriptor.parameters);
+        let load = |file: &str| match file == shader_file {
+            true => Some(complete_source.clone()),
+            false => shader_preprocessor::embedded_source(file).map(str::to_string),
+        };
+        shader_preprocessor::preprocess(&shader_file, &load).map_err(|error| load_error(&error))?;
+
+        self.device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let effect: Box<dyn Effect> = Box::new(descriptor.create(
+            &self.device,
+            &self.queue,
+            TEXTURE_FORMAT,
+            self.size,
+            self.post_processor.globals_bind_group_layout(),
+        ));
+        if let Some(error) = pollster::block_on(self.device.pop_error_scope()) {
+            return Err(load_error(&error));
+        }
+
+        Ok(self.post_processor.register(descriptor, effect))
+    }
+
+    /// adds an effect that implements `Effect` in Rust and returns its effect index, see `PostProcessor::add_effect`.
+    /// The effect is created for `TEXTURE_FORMAT` and the current frame size.
+    pub fn add_effect(&mut self, name: &str, effect: Box<dyn Effect>) -> usize {
+        self.post_processor.add_effect(name, effect)
+    }
+
+    /// the parameters of the effect with their current values and the metadata of their annotations.
+    /// `None` if the index is unknown or the effect has no parameters, the effect is created if it wasn't used yet.
+    pub fn parameters(&mut self, effect_index: usize) -> Option<&EffectParameters> {
+        self.post_processor
+            .create_effect(&self.device, &self.queue, effect_index)
+            .ok()?
+            .parameters()
+    }
+
+    /// changes a parameter of the effect for the following frames, the values are clamped to the annotated range
+    pub fn set_parameter(&mut self, effect_index: usize, name: &str, values: &[f32]) -> Result<(), HeadlessError> {
+        self.post_processor
+            .create_effect(&self.device, &self.queue, effect_index)?
+            .parameters_mut()
+            .ok_or_else(|| ParameterError::UnknownParameter(name.to_string()))
+            .and_then(|parameters| parameters.set(name, values))
+            .map_err(HeadlessError::Parameter)
+    }
+
+    /// replaces the lookup table of an effect of the `Lut` kind, returns false if the effect doesn't use a table
+    pub fn set_lut(&mut self, effect_index: usize, lut: &CubeLut) -> Result<bool, HeadlessError> {
+        let lut = LutTexture::new(&self.device, &self.queue, lut, "lut").map_err(HeadlessError::Lut)?;
+        Ok(self
+            .post_processor
+            .create_effect(&self.device, &self.queue, effect_index)?
+            .as_post_processing_mut()
+            .is_some_and(|effect| effect.set_lut(lut)))
+    }
+
+    /// replaces the scene with the given image. The rendered frames take on the size of the image.
+    /// An empty image is an error and keeps the previous scene.
+    pub fn set_scene_image(&mut self, img: &image::DynamicImage, label: Option<&str>) -> Result<(), HeadlessError> {
+        frame_size(&self.device, img.width(), img.height())?;
+        self.scene = Box::new(TextureScene::from_image(
+            &self.device,
+            &self.queue,
+            TEXTURE_FORMAT,
+            self.post_processor.globals_bind_group_layout(),
+            img,
+            label,
+        ));
+        self.resize(img.width(), img.height())
+    }
+
+    /// replaces the scene with any scene source, e.g. a `ProceduralScene` or a `FrameBufferScene`.
+    /// Scenes are created for `TEXTURE_FORMAT` and drawn over the whole frame, the frame size doesn't change.
+    pub fn set_scene(&mut self, scene: Box<dyn Scene>) {
+        self.scene = scene;
+    }
+
+    /// changes the size of the rendered frames
+    pub fn resize(&mut self, width: u32, height: u32) -> Result<(), HeadlessError> {
+        let size = frame_size(&self.device, width, height)?;
+        if size != self.size {
+            self.size = size;
+            self.scene_target = RenderTarget::new(&self.device, size, TEXTURE_FORMAT, "initial render pass canvas");
+            self.output_texture = create_output_texture(&self.device, size);
+            self.post_processor.set_input(&self.device, self.scene_target.view(), size);
+        }
+        Ok(())
+    }
+
+    /// renders the scene, applies the effects in the given order and returns the final frame.
+    /// Without any effects the unprocessed scene is returned. The resolution and aspect of the globals are set to the frame size.
+    pub fn render(&mut self, effect_indices: &[usize], globals: &Globals) -> Result<image::RgbaImage, HeadlessError> {
+        let mut globals = *globals;
+        globals.set_resolution(self.size.width, self.size.height);
+        *self.post_processor.globals_mut() = globals;
+        self.scene.update(&self.queue, &globals);
+        self.post_processor.update(&self.queue);
+
+        let output_view = self.output_texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Headless Render Encoder"),
+        });
+
+        if effect_indices.is_empty() {
+            self.scene
+                .encode(&mut encoder, &output_view, self.post_processor.globals_bind_group());
+        } else {
+            self.scene
+                .encode(&mut encoder, self.scene_target.view(), self.post_processor.globals_bind_group());
+
+            self.post_processor.set_chain(&self.device, &self.queue, effect_indices.to_vec())?;
+            self.post_processor.encode(&mut encoder, &output_view);
+        }
+
+        // rows of a texture to buffer copy have to be aligned to COPY_BYTES_PER_ROW_ALIGNMENT
+        let unpadded_bytes_per_row = 4 * self.size.width;
+        let padded_bytes_per_row = unpadded_bytes_per_row.div_ceil(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT) * wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+
+        let output_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("headless output buffer"),
+            size: (padded_bytes_per_row * self.size.height) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        encoder.copy_texture_to_buffer(
+            wgpu::ImageCopyTexture {
+                aspect: wgpu::TextureAspect::All,
+                texture: &self.output_texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+            },
+            wgpu::ImageCopyBuffer {
+                buffer: &output_buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(self.size.height),
+                },
+            },
+            self.size,
+        );
+
+        self.queue.submit(std::iter::once(encoder.finish()));
+
+        let buffer_slice = output_buffer.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        self.device.poll(wgpu::Maintain::Wait);
+        receiver
+            .recv()
+            .expect("buffer mapping callback was dropped")
+            .map_err(HeadlessError::BufferMap)?;
+
+        let mut pixels = Vec::with_capacity((unpadded_bytes_per_row * self.size.height) as usize);
+        {
+            let padded_data = buffer_slice.get_mapped_range();
+            for row in padded_data.chunks(padded_bytes_per_row as usize) {
+                pixels.extend_from_slice(&row[..unpadded_bytes_per_row as usize]);
+            }
+        }
+        output_buffer.unmap();
+
+        Ok(image::RgbaImage::from_raw(self.size.width, self.size.height, pixels).expect("pixel buffer matches the image size"))
+    }
+}
+
+/// the size of the frame textures, wgpu doesn't allow empty textures or textures beyond the limit of the device
+fn frame_size(device: &wgpu::Device, width: u32, height: u32) -> Result<wgpu::Extent3d, HeadlessError> {
+    let max_size = device.limits().max_texture_dimension_2d;
+    if width == 0 || height == 0 || width > max_size || height > max_size {
+        return Err(HeadlessError::InvalidSize { width, height });
+    }
+    Ok(wgpu::Extent3d {
+        width,
+        height,
+        depth_or_array_layers: 1,
+    })
+}
+
+/// creates the texture that holds the final frame and is copied back into CPU memory
+fn create_output_texture(device: &wgpu::Device, size: wgpu::Extent3d) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("headless output"),
+        dimension: wgpu::TextureDimension::D2,
+        format: TEXTURE_FORMAT,
+        mip_level_count: 1,
+        sample_count: 1,
+        size,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+        view_formats: &[],
+    })
+}
@@ -0,0 +1,179 @@
+//! native only dev mode that reloads the post processing shaders from `src/shaders` while the app is running.
+//! Enable it by setting the `SHADER_HOT_RELOAD` environment variable, e.g. `SHADER_HOT_RELOAD=1 cargo run`
+
+use crate::effect::Effect;
+use crate::effect_parameters::ParameterLayout;
+use crate::effect_registry::{EffectKind, EffectRegistry};
+use crate::shader_preprocessor;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+use wgpu::naga;
+
+const ENV_VAR: &str = "SHADER_HOT_RELOAD";
+const SHADER_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/shaders");
+/// checking the modification times on every frame would be unnecessary work
+const CHECK_INTERVAL: Duration = Duration::from_millis(250);
+
+pub struct ShaderWatcher {
+    shaders: Vec<WatchedShader>,
+    last_check: Option<Instant>,
+}
+
+struct WatchedShader {
+    effect_index: usize,
+    kind: EffectKind,
+    file: String,
+    /// the shader file followed by the files it includes
+    files: Vec<PathBuf>,
+    /// modification times of `files` when the shader was loaded
+    modified: Vec<Option<SystemTime>>,
+}
+
+impl ShaderWatcher {
+    /// returns a watcher for the shader files of all registered effects if the dev mode is enabled
+    pub fn from_env(effect_registry: &EffectRegistry) -> Option<Self> {
+        std::env::var_os(ENV_VAR)?;
+        log::info!("hot reloading shaders from {SHADER_DIR}");
+
+        let shaders = effect_registry
+            .descriptors()
+            .map(|(effect_index, descriptor)| {
+                // the effects are created from the embedded shaders, which are assumed to match the files at startup
+                let complete_source = descriptor.kind.complete_source(&descriptor.shader_source, &descriptor.parameters);
+                let load = |file: &str| match file == descriptor.shader_file {
+                    true => Some(complete_source.clone()),
+                    false => shader_preprocessor::embedded_source(file).map(str::to_string),
+                };
+                let included_files = shader_preprocessor::preprocess(&descriptor.shader_file, &load)
+                    .map(|preprocessed| preprocessed.files)
+                    .unwrap_or_else(|_| vec![descriptor.shader_file.clone()]);
+                let files: Vec<_> = included_files.iter().map(|file| Path::new(SHADER_DIR).join(file)).collect();
+                WatchedShader {
+                    effect_index,
+                    kind: descriptor.kind,
+                    file: descriptor.shader_file.clone(),
+                    modified: modification_times(&files),
+                    files,
+                }
+            })
+            .collect();
+
+        Some(Self { shaders, last_check: None })
+    }
+
+    /// reloads every effect whose shader file or one of its included files changed since the last call.
+    /// Shaders that fail to compile are logged and the effect keeps its last working pipeline.
+    pub fn reload_changed(&mut self, device: &wgpu::Device, effects: &mut [Option<Box<dyn Effect>>]) {
+        if self.last_check.is_some_and(|last_check| last_check.elapsed() < CHECK_INTERVAL) {
+            return;
+        }
+        self.last_check = Some(Instant::now());
+
+        for shader in &mut self.shaders {
+            // only shader effects are watched, an effect that was replaced by an effect in Rust is skipped.
+            // Effects that were not created yet keep their modification times, they are compared once the effect exists.
+            let Some(effect) = effects[shader.effect_index]
+                .as_deref_mut()
+                .and_then(|effect| effect.as_post_processing_mut())
+            else {
+                continue;
+            };
+            let modified = modification_times(&shader.files);
+            if shader.modified == modified {
+                continue;
+            }
+
+            shader.modified = modified;
+            let label = shader.file.as_str();
+            let main_source = match std::fs::read_to_string(&shader.files[0]) {
+                Ok(main_source) => main_source,
+                Err(error) => {
+                    log::error!("keeping the previous pipeline, could not read {label}: {error}");
+                    continue;
+                }
+            };
+
+            let parameters = match ParameterLayout::parse(&main_source) {
+                Ok(parameters) => parameters,
+                Err(error) => {
+                    log::error!("keeping the previous pipeline, {label}: {error}");
+                    continue;
+                }
+            };
+            // changed ranges and defaults only take effect after a restart, changed names or types would need a new uniform buffer
+            let current_parameters = effect
+                .parameters()
+                .map(|parameters| parameters.layout().clone())
+                .unwrap_or_default();
+            if parameters.wgsl_declarations() != current_parameters.wgsl_declarations() {
+                log::error!("keeping the previous pipeline, the @param names or types of {label} changed, restart to apply them");
+                continue;
+            }
+            // the targets and pipelines of the passes are created once with the effect
+            let passes = match shader.kind.parse_passes(&main_source) {
+                Ok(passes) => passes,
+                Err(error) => {
+                    log::error!("keeping the previous pipeline, {label}: {error}");
+                    continue;
+                }
+            };
+            if effect.passes().cloned().unwrap_or_default() != passes {
+                log::error!("keeping the previous pipeline, the @target or @pass annotations of {label} changed, restart to apply them");
+                continue;
+            }
+
+            let complete_source = shader.kind.complete_source(&main_source, &parameters);
+            let load = |file: &str| match file == label {
+                true => Some(complete_source.clone()),
+                false => std::fs::read_to_string(Path::new(SHADER_DIR).join(file)).ok(),
+            };
+            let source = match shader_preprocessor::preprocess(label, &load) {
+                Ok(preprocessed) => {
+                    shader.files = preprocessed.files.iter().map(|file| Path::new(SHADER_DIR).join(file)).collect();
+                    shader.modified = modification_times(&shader.files);
+                    preprocessed.source
+                }
+                Err(error) => {
+                    log::error!("keeping the previous pipeline, {error}");
+                    continue;
+                }
+            };
+
+            if let Err(error) = validate(&source, label) {
+                log::error!("keeping the previous pipeline, {label} failed to compile:\n{error}");
+                continue;
+            }
+
+            // naga accepts shaders that don't fit the post processing pipeline, e.g. with a renamed entry point
+            device.push_error_scope(wgpu::ErrorFilter::Validation);
+            let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: Some(label),
+                source: wgpu::ShaderSource::Wgsl(source.into()),
+            });
+            let pipeline = effect.create_pipeline(device, &shader_module);
+            match pollster::block_on(device.pop_error_scope()) {
+                None => {
+                    effect.set_pipeline(pipeline);
+                    log::info!("reloaded {label}");
+                }
+                Some(error) => log::error!("keeping the previous pipeline, {label} does not fit the post processing pipeline:\n{error}"),
+            }
+        }
+    }
+}
+
+fn modification_times(files: &[PathBuf]) -> Vec<Option<SystemTime>> {
+    files
+        .iter()
+        .map(|file| std::fs::metadata(file).and_then(|metadata| metadata.modified()).ok())
+        .collect()
+}
+
+/// parses and validates the WGSL source with naga, the error contains the formatted naga diagnostics
+fn validate(source: &str, path: &str) -> Result<(), String> {
+    let module = naga::front::wgsl::parse_str(source).map_err(|error| error.emit_to_string_with_path(source, path))?;
+    naga::valid::Validator::new(naga::valid::ValidationFlags::all(), naga::valid::Capabilities::all())
+        .validate(&module)
+        .map_err(|error| error.emit_to_string_with_path(source, path))?;
+    Ok(())
+}
@@ -1,13 +1,32 @@
-mod post_processing;
-mod scene;
+pub mod config;
+pub mod effect;
+pub mod effect_parameters;
+mod effect_passes;
+mod effect_registry;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod headless;
+#[cfg(not(target_arch = "wasm32"))]
+mod hot_reload;
+pub mod lut;
+pub mod post_processing;
+mod post_processing_chain;
+pub mod post_processor;
+pub mod preset;
+pub mod render_target;
+pub mod scene;
 mod shader_globals;
+mod shader_preprocessor;
 mod texture;
 
+pub use effect_registry::EffectRegistry;
+pub use shader_globals::Globals;
+
 use chrono::{DateTime, Utc};
-use post_processing::PostProcessing;
-use scene::Scene;
-use shader_globals::Globals;
-use wgpu::util::DeviceExt;
+use config::{Config, ConfigError};
+use post_processor::PostProcessor;
+use preset::Preset;
+use render_target::RenderTarget;
+use scene::{Scene, TextureScene};
 use winit::{
     event::*,
     event_loop::EventLoop,
@@ -18,8 +37,34 @@ use winit::{
 #[cfg(target_arch = "wasm32")]
 use wasm_bindgen::prelude::*;
 
+#[cfg(target_arch = "wasm32")]
+thread_local! {
+    /// presets that were passed in with `load_preset` and are added to the demo with the next update
+    static LOADED_PRESETS: std::cell::RefCell<Vec<Preset>> = Default::default();
+}
+
+/// adds a preset in the format of `preset.rs` to the presets of the demo and shows it.
+/// Fails with the line and the key of the first invalid entry.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn load_preset(source: &str) -> Result<(), JsValue> {
+    let preset = Preset::parse(source, &EffectRegistry::built_in()).map_err(|error| JsValue::from_str(&error.to_string()))?;
+    LOADED_PRESETS.with(|presets| presets.borrow_mut().push(preset));
+    Ok(())
+}
+
 #[cfg_attr(target_arch = "wasm32", wasm_bindgen(start))]
 pub async fn run() {
+    run_with_config(Config::default())
+        .await
+        .expect("the default config has no scene image that could fail to load");
+}
+
+/// opens the window with the scene, effects and window size of the config.
+/// Fails if the scene image of the config can't be loaded.
+// the event loop keeps the nesting of the tutorial it is based on
+#[allow(clippy::collapsible_match)]
+pub async fn run_with_config(config: Config) -> Result<(), ConfigError> {
     cfg_if::cfg_if! {
         if #[cfg(target_arch = "wasm32")] {
             std::panic::set_hook(Box::new(console_error_panic_hook::hook));
@@ -29,7 +74,17 @@ pub async fn run() {
         }
     }
 
-    let size = winit::dpi::PhysicalSize { width: 800, height: 220 };
+    let scene_image = match &config.scene {
+        Some(path) => Some(image::open(path).map_err(|error| ConfigError::Invalid {
+            line: 0,
+            key: "scene".to_string(),
+            message: format!("could not load {}, {error}", path.display()),
+        })?),
+        None => None,
+    };
+
+    let (width, height) = config.window_size.unwrap_or(config::DEFAULT_WINDOW_SIZE);
+    let size = winit::dpi::PhysicalSize { width, height };
 
     let event_loop = EventLoop::new().unwrap();
     let window = WindowBuilder::new()
@@ -56,7 +111,7 @@ pub async fn run() {
             .expect("Couldn't append canvas to document body.");
     }
 
-    let mut state = State::new(&window).await;
+    let mut state = State::new(&window, &config, scene_image).await;
     let mut surface_configured = false;
 
     event_loop
@@ -78,8 +133,8 @@ pub async fn run() {
                         }
                         WindowEvent::CursorMoved { position, .. } => {
                             let size = state.window.inner_size();
-                            state.globals.cursor_x = (position.x as f32 / size.width as f32) * 2. - 1.;
-                            state.globals.cursor_y = (position.y as f32 / size.height as f32) * 2. - 1.;
+                            state.post_processor.globals_mut().cursor_x = (position.x as f32 / size.width as f32) * 2. - 1.;
+                            state.post_processor.globals_mut().cursor_y = (position.y as f32 / size.height as f32) * 2. - 1.;
                         }
                         WindowEvent::Touch(touch) => match touch.phase {
                             TouchPhase::Started => state.last_touch_start = chrono::Utc::now(),
@@ -91,11 +146,51 @@ pub async fn run() {
                             TouchPhase::Moved => {
                                 let position = touch.location;
                                 let size = state.window.inner_size();
-                                state.globals.cursor_x = (position.x as f32 / size.width as f32) * 2. - 1.;
-                                state.globals.cursor_y = (position.y as f32 / size.height as f32) * 2. - 1.;
+                                state.post_processor.globals_mut().cursor_x = (position.x as f32 / size.width as f32) * 2. - 1.;
+                                state.post_processor.globals_mut().cursor_y = (position.y as f32 / size.height as f32) * 2. - 1.;
                             }
                             _ => {}
                         },
+                        WindowEvent::KeyboardInput {
+                            event:
+                                KeyEvent {
+                                    state: ElementState::Pressed,
+                                    physical_key: PhysicalKey::Code(KeyCode::Space),
+                                    repeat: false,
+                                    ..
+                                },
+                            ..
+                        } => toggle_stacked_post_processing_effects(&mut state),
+                        WindowEvent::KeyboardInput {
+                            event:
+                                KeyEvent {
+                                    state: ElementState::Pressed,
+                                    physical_key: PhysicalKey::Code(key_code @ (KeyCode::ArrowUp | KeyCode::ArrowDown)),
+                                    ..
+                                },
+                            ..
+                        } => scale_effect_strength(&mut state, if *key_code == KeyCode::ArrowUp { 1.25 } else { 0.8 }),
+                        WindowEvent::KeyboardInput {
+                            event:
+                                KeyEvent {
+                                    state: ElementState::Pressed,
+                                    physical_key: PhysicalKey::Code(KeyCode::KeyP),
+                                    repeat: false,
+                                    ..
+                                },
+                            ..
+                        } => cycle_presets(&mut state),
+                        #[cfg(not(target_arch = "wasm32"))]
+                        WindowEvent::KeyboardInput {
+                            event:
+                                KeyEvent {
+                                    state: ElementState::Pressed,
+                                    physical_key: PhysicalKey::Code(KeyCode::KeyS),
+                                    repeat: false,
+                                    ..
+                                },
+                            ..
+                        } => save_preset(&mut state),
                         WindowEvent::CloseRequested
                         | WindowEvent::KeyboardInput {
                             event:
@@ -142,10 +237,93 @@ pub async fn run() {
             _ => {}
         })
         .unwrap();
+    Ok(())
 }
 
+/// switches to the next single post processing effect
 fn change_post_processing_effect(state: &mut State) {
-    state.current_post_processing_index = (state.current_post_processing_index + 1) % state.post_processing_effects.len();
+    // the stacked effects are filtered by name, so the chain may be empty
+    let next_index = match state.post_processor.chain().first() {
+        Some(current_index) => (current_index + 1) % state.post_processor.effect_count(),
+        None => 0,
+    };
+    state
+        .post_processor
+        .set_chain(&state.device, &state.queue, vec![next_index])
+        .expect("the next index is below the effect count");
+    state.frozen_time = None;
+}
+
+/// switches between a single post processing effect and all effects stacked on top of each other
+fn toggle_stacked_post_processing_effects(state: &mut State) {
+    state.frozen_time = None;
+    if state.post_processor.chain().len() > 1 {
+        state
+            .post_processor
+            .set_chain(&state.device, &state.queue, vec![0])
+            .expect("there are built-in effects");
+    } else {
+        let stacked_effects = ["wave_distortion", "invert_color", "droplet"];
+        let effect_indices = stacked_effects
+            .iter()
+            .filter_map(|name| state.post_processor.effect_index(name))
+            .collect();
+        state
+            .post_processor
+            .set_chain(&state.device, &state.queue, effect_indices)
+            .expect("the indices were looked up by name");
+    }
+}
+
+/// applies the next preset, the built-in presets are followed by the files of the presets directory
+fn cycle_presets(state: &mut State) {
+    if state.presets.is_empty() {
+        return;
+    }
+    let next_index = state
+        .preset_index
+        .map_or(0, |preset_index| (preset_index + 1) % state.presets.len());
+    state.select_preset(next_index);
+}
+
+/// saves the active effects, their parameters and the cursor position as a new preset into the presets directory
+#[cfg(not(target_arch = "wasm32"))]
+fn save_preset(state: &mut State) {
+    let name = format!("preset_{}", chrono::Utc::now().format("%Y%m%d_%H%M%S"));
+    let path = state.presets_dir.join(format!("{name}.toml"));
+    let preset = Preset::capture(name, &state.post_processor);
+    match preset.save(&path) {
+        Ok(()) => {
+            log::info!("saved the preset {}", path.display());
+            state.presets.push(preset);
+            state.preset_index = Some(state.presets.len() - 1);
+        }
+        Err(error) => log::error!("could not save the preset {}: {error}", path.display()),
+    }
+}
+
+/// changes the strength of the active effects that have parameters
+fn scale_effect_strength(state: &mut State, factor: f32) {
+    for effect_index in state.post_processor.chain().to_vec() {
+        let strength_parameter = match state.post_processor.effect_name(effect_index) {
+            Some("wave_distortion") => "amplitude",
+            Some("droplet") => "fall_off_distance",
+            _ => continue,
+        };
+        let Some(parameters) = state
+            .post_processor
+            .effect_mut(effect_index)
+            .and_then(|effect| effect.parameters_mut())
+        else {
+            continue;
+        };
+        if let Some(strength) = parameters.get(strength_parameter) {
+            // the value is clamped to the range of the @param annotation
+            parameters
+                .set(strength_parameter, &[strength[0] * factor])
+                .expect("strength parameters are declared as f32");
+        }
+    }
 }
 
 use winit::window::Window;
@@ -161,18 +339,25 @@ struct State<'a> {
     // unsafe references to the window's resources.
     window: &'a Window,
     start_time: DateTime<Utc>,
-    globals: Globals,
-    globals_buffer: wgpu::Buffer,
-    globals_bind_group: wgpu::BindGroup,
-    scene: Scene,
-    post_processing_effects: Vec<PostProcessing>,
-    current_post_processing_index: usize,
+    scene: Box<dyn Scene>,
+    // the scene is rendered to a texture that only lives in memory and is not displayed on the screen
+    scene_target: RenderTarget,
+    post_processor: PostProcessor,
     last_touch_start: DateTime<Utc>,
+    presets: Vec<Preset>,
+    /// the preset that was selected last, the effects may have been changed since
+    preset_index: Option<usize>,
+    /// the time of the selected preset, the animations stop at this time
+    frozen_time: Option<f32>,
+    #[cfg(not(target_arch = "wasm32"))]
+    presets_dir: std::path::PathBuf,
+    #[cfg(not(target_arch = "wasm32"))]
+    shader_watcher: Option<hot_reload::ShaderWatcher>,
 }
 
 impl<'a> State<'a> {
     // Creating some of the wgpu types requires async code
-    async fn new(window: &'a Window) -> State<'a> {
+    async fn new(window: &'a Window, app_config: &Config, scene_image: Option<image::DynamicImage>) -> State<'a> {
         let size = window.inner_size();
 
         // The instance is a handle to our GPU
@@ -237,52 +422,39 @@ impl<'a> State<'a> {
         };
 
         let start_time = chrono::Utc::now();
-        let globals = Globals::new();
-
-        let globals_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("globals buffer"),
-            contents: bytemuck::bytes_of(&globals),
-            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-        });
-
-        let globals_bind_group_layout = device.create_bind_group_layout(shader_globals::BIND_GROUP_LAYOUT);
 
-        let globals_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("globals bind group"),
-            layout: &globals_bind_group_layout,
-            entries: &[wgpu::BindGroupEntry {
-                binding: 0,
-                resource: globals_buffer.as_entire_binding(),
-            }],
-        });
+        // the scene is rendered to the scene target, which is the input of the post processing effects
+        let scene_target = RenderTarget::new(&device, render_target_size(&config), config.format, "initial render pass canvas");
+        let mut post_processor = PostProcessor::new(&device, config.format, scene_target.view(), render_target_size(&config));
+        post_processor
+            .set_chain(&device, &queue, vec![0])
+            .expect("there are built-in effects");
+        post_processor.globals_mut().seed = start_time.timestamp_subsec_nanos();
+        app_config
+            .apply(&device, &queue, &mut post_processor)
+            .expect("the config is checked against the built-in effects when it is parsed");
+
+        let mut scene = match scene_image {
+            Some(img) => TextureScene::from_image(
+                &device,
+                &queue,
+                config.format,
+                post_processor.globals_bind_group_layout(),
+                &img,
+                Some("scene image"),
+            ),
+            None => TextureScene::new(&device, &queue, config.format, post_processor.globals_bind_group_layout()),
+        };
+        if let Some(filter_mode) = app_config.sampler {
+            scene.set_filter_mode(&device, filter_mode);
+        }
 
-        let scene = Scene::new(&device, &queue, config.format, &globals_bind_group_layout);
-
-        let mut post_processing_effects = Vec::new();
-
-        let invert_color_shader = device.create_shader_module(wgpu::include_wgsl!("shaders/post_processing_invert_color.wgsl"));
-        post_processing_effects.push(PostProcessing::new(
-            &device,
-            config.format,
-            &globals_bind_group_layout,
-            invert_color_shader,
-        ));
-
-        let wave_distortion_shader = device.create_shader_module(wgpu::include_wgsl!("shaders/post_processing_wave_distortion.wgsl"));
-        post_processing_effects.push(PostProcessing::new(
-            &device,
-            config.format,
-            &globals_bind_group_layout,
-            wave_distortion_shader,
-        ));
-
-        let droplet_shader = device.create_shader_module(wgpu::include_wgsl!("shaders/post_processing_droplet.wgsl"));
-        post_processing_effects.push(PostProcessing::new(
-            &device,
-            config.format,
-            &globals_bind_group_layout,
-            droplet_shader,
-        ));
+        #[cfg_attr(target_arch = "wasm32", allow(unused_mut))]
+        let mut presets = Preset::built_in();
+        #[cfg(not(target_arch = "wasm32"))]
+        let presets_dir = app_config.presets.clone().unwrap_or_else(|| "presets".into());
+        #[cfg(not(target_arch = "wasm32"))]
+        presets.extend(Preset::load_dir(&presets_dir, post_processor.effect_registry()));
 
         Self {
             window,
@@ -292,13 +464,17 @@ impl<'a> State<'a> {
             config,
             size,
             start_time,
-            globals,
-            globals_buffer,
-            globals_bind_group,
-            scene,
-            post_processing_effects,
-            current_post_processing_index: 0,
+            scene: Box::new(scene),
+            scene_target,
+            #[cfg(not(target_arch = "wasm32"))]
+            shader_watcher: hot_reload::ShaderWatcher::from_env(post_processor.effect_registry()),
+            post_processor,
             last_touch_start: start_time,
+            presets,
+            preset_index: None,
+            frozen_time: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            presets_dir,
         }
     }
 
@@ -312,50 +488,83 @@ impl<'a> State<'a> {
             self.config.width = new_size.width;
             self.config.height = new_size.height;
             self.surface.configure(&self.device, &self.config);
+
+            // the render targets are only recreated here and not for every frame
+            let size = render_target_size(&self.config);
+            if !self.scene_target.matches(size, self.config.format) {
+                self.scene_target = RenderTarget::new(&self.device, size, self.config.format, "initial render pass canvas");
+                self.post_processor.set_input(&self.device, self.scene_target.view(), size);
+            }
         }
     }
 
-    fn input(&mut self, _event: &WindowEvent) -> bool {
+    /// keeps track of the button state in the globals, returns whether the event was consumed
+    fn input(&mut self, event: &WindowEvent) -> bool {
+        match event {
+            WindowEvent::MouseInput { state, button, .. } => {
+                let button = match button {
+                    MouseButton::Left => shader_globals::BUTTON_LEFT,
+                    MouseButton::Right => shader_globals::BUTTON_RIGHT,
+                    MouseButton::Middle => shader_globals::BUTTON_MIDDLE,
+                    _ => return false,
+                };
+                self.post_processor.globals_mut().set_button(button, state.is_pressed());
+            }
+            WindowEvent::Touch(touch) => {
+                let touching = matches!(touch.phase, TouchPhase::Started | TouchPhase::Moved);
+                self.post_processor.globals_mut().set_button(shader_globals::BUTTON_TOUCH, touching);
+            }
+            _ => {}
+        }
         false
     }
 
     fn update(&mut self) {
-        self.globals.time = (chrono::Utc::now() - self.start_time).num_milliseconds() as f32 / 1000.;
-        self.queue.write_buffer(&self.globals_buffer, 0, bytemuck::bytes_of(&self.globals));
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(shader_watcher) = &mut self.shader_watcher {
+            shader_watcher.reload_changed(&self.device, self.post_processor.effects_mut());
+        }
+
+        #[cfg(target_arch = "wasm32")]
+        for preset in LOADED_PRESETS.with(|presets| presets.take()) {
+            self.presets.push(preset);
+            self.select_preset(self.presets.len() - 1);
+        }
+
+        let time = self
+            .frozen_time
+            .unwrap_or_else(|| (chrono::Utc::now() - self.start_time).num_milliseconds() as f32 / 1000.);
+        self.post_processor.globals_mut().set_time(time);
+        self.scene.update(&self.queue, self.post_processor.globals());
+        self.post_processor.update(&self.queue);
+    }
+
+    fn select_preset(&mut self, preset_index: usize) {
+        let preset = &self.presets[preset_index];
+        match preset.apply(&self.device, &self.queue, &mut self.post_processor) {
+            Ok(()) => {
+                log::info!("preset {}", preset.name);
+                self.preset_index = Some(preset_index);
+                self.frozen_time = preset.time;
+            }
+            Err(error) => log::error!("could not apply the preset {}: {error}", preset.name),
+        }
     }
 
     fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
         let screen = self.surface.get_current_texture()?;
-        let screen_texture = &screen.texture;
-        let screen_view = screen_texture.create_view(&wgpu::TextureViewDescriptor { ..Default::default() });
-
-        // create a view that only lives in memory and is not displayed on the screen
-        let in_memory_texture = self.device.create_texture(&wgpu::TextureDescriptor {
-            label: Some("initial render pass canvas"),
-            dimension: screen_texture.dimension(),
-            format: screen_texture.format(),
-            mip_level_count: screen_texture.mip_level_count(),
-            sample_count: screen_texture.sample_count(),
-            size: screen_texture.size(),
-            usage: screen_texture.usage() | wgpu::TextureUsages::TEXTURE_BINDING,
-            view_formats: &[],
-        });
-        let in_memory_view = in_memory_texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let screen_view = screen.texture.create_view(&wgpu::TextureViewDescriptor { ..Default::default() });
+
         let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
             label: Some("Render Encoder"),
         });
 
         // first render pass - create the scene
-        self.scene.render_pass(&mut encoder, &in_memory_view, &self.globals_bind_group)?;
+        self.scene
+            .encode(&mut encoder, self.scene_target.view(), self.post_processor.globals_bind_group());
 
-        // second render pass - apply post processing effects to the scene
-        self.post_processing_effects[self.current_post_processing_index].render_pass(
-            &self.device,
-            &mut encoder,
-            &in_memory_view,
-            &screen_view,
-            &self.globals_bind_group,
-        )?;
+        // following render passes - apply the chain of post processing effects to the scene
+        self.post_processor.encode(&mut encoder, &screen_view);
 
         self.queue.submit(std::iter::once(encoder.finish()));
         screen.present();
@@ -363,3 +572,12 @@ impl<'a> State<'a> {
         Ok(())
     }
 }
+
+/// size of the textures that have the same size as the surface. Textures can't be empty, e.g. before the first resize on the web.
+fn render_target_size(config: &wgpu::SurfaceConfiguration) -> wgpu::Extent3d {
+    wgpu::Extent3d {
+        width: config.width.max(1),
+        height: config.height.max(1),
+        depth_or_array_layers: 1,
+    }
+}
@@ -0,0 +1,465 @@
+//! 3D color lookup tables in the `.cube` format of Adobe and DaVinci Resolve, used by the effects of the `Lut` kind.
+//!
+//! the table is uploaded as a 3D texture and sampled with trilinear filtering, see `shaders/lut.wgsl`.
+//! The WebGL build unwraps the table into a 2D strip of slices next to each other instead, see `shaders/lut_strip.wgsl`.
+
+use crate::texture::Texture;
+use wgpu::util::DeviceExt;
+
+/// the look of the built in `color_grading` effect
+pub const DEFAULT_LUT: &str = include_str!("luts/teal_orange.cube");
+
+/// how the table is stored on the GPU
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LutLayout {
+    /// a `size`³ 3D texture
+    Volume,
+    /// a 2D texture of `size * size` by `size` pixels with the blue slices next to each other
+    Strip,
+}
+
+impl LutLayout {
+    /// the layout of this build, has to match the `lut.wgsl` that is embedded by the shader preprocessor
+    pub const CURRENT: Self = if cfg!(target_arch = "wasm32") {
+        LutLayout::Strip
+    } else {
+        LutLayout::Volume
+    };
+
+    fn view_dimension(self) -> wgpu::TextureViewDimension {
+        match self {
+            LutLayout::Volume => wgpu::TextureViewDimension::D3,
+            LutLayout::Strip => wgpu::TextureViewDimension::D2,
+        }
+    }
+}
+
+/// layout of the table at group 3 of effects of the `Lut` kind, see `LutTexture::bind_group`
+pub fn bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("lut bind group layout"),
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    multisampled: false,
+                    view_dimension: LutLayout::CURRENT.view_dimension(),
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 2,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+        ],
+    })
+}
+
+#[derive(Debug)]
+pub enum LutError {
+    /// `line` is 0 for errors that don't belong to a line, e.g. a missing size
+    Invalid { line: usize, message: String },
+    /// 1D tables are not supported
+    Unsupported1D,
+    /// the texture of the table is larger than the device supports
+    TooLarge { size: u32, max_dimension: u32 },
+}
+
+impl std::fmt::Display for LutError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LutError::Invalid { line: 0, message } => write!(f, "invalid .cube file, {message}"),
+            LutError::Invalid { line, message } => write!(f, "line {line}: invalid .cube file, {message}"),
+            LutError::Unsupported1D => write!(f, "1D lookup tables are not supported, only LUT_3D_SIZE"),
+            LutError::TooLarge { size, max_dimension } => {
+                write!(
+                    f,
+                    "LUT_3D_SIZE {size} is too large, the textures of the device are at most {max_dimension} pixels wide"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for LutError {}
+
+/// a parsed `.cube` file
+#[derive(Debug, Clone, PartialEq)]
+pub struct CubeLut {
+    pub title: Option<String>,
+    /// number of points along each axis
+    pub size: u32,
+    /// the input values that map to the first and last points
+    pub domain_min: [f32; 3],
+    pub domain_max: [f32; 3],
+    /// `size`³ output colors, red changes fastest and blue slowest
+    pub data: Vec<[f32; 3]>,
+}
+
+impl CubeLut {
+    pub fn parse(source: &str) -> Result<Self, LutError> {
+        let mut title = None;
+        let mut size = None;
+        let mut domain_min = [0.; 3];
+        let mut domain_max = [1.; 3];
+        let mut data = Vec::new();
+
+        for (line_index, line) in source.lines().enumerate() {
+            let error = |message: String| LutError::Invalid {
+                line: line_index + 1,
+                message,
+            };
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (keyword, arguments) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            let numbers = |count: usize| -> Result<Vec<f32>, LutError> {
+                let numbers: Vec<f32> = arguments
+                    .split_whitespace()
+                    .map(str::parse)
+                    .collect::<Result<_, _>>()
+                    .map_err(|_| error(format!("expected numbers after {keyword}")))?;
+                match numbers.len() == count {
+                    true => Ok(numbers),
+                    false => Err(error(format!("expected {count} numbers after {keyword}"))),
+                }
+            };
+
+            match keyword {
+                "TITLE" => title = Some(arguments.trim().trim_matches('"').to_string()),
+                "LUT_1D_SIZE" => return Err(LutError::Unsupported1D),
+                "LUT_3D_SIZE" => {
+                    let points = arguments
+                        .trim()
+                        .parse::<u32>()
+                        .ok()
+                        .filter(|points| (2..=256).contains(points))
+                        .ok_or_else(|| error("LUT_3D_SIZE has to be a number from 2 to 256".to_string()))?;
+                    size = Some(points);
+                }
+                "DOMAIN_MIN" => domain_min.copy_from_slice(&numbers(3)?),
+                "DOMAIN_MAX" => domain_max.copy_from_slice(&numbers(3)?),
+                // the Resolve variant of the domain with the same range for all channels
+                "LUT_3D_INPUT_RANGE" => {
+                    let range = numbers(2)?;
+                    domain_min = [range[0]; 3];
+                    domain_max = [range[1]; 3];
+                }
+                _ if keyword.starts_with(|c: char| c.is_ascii_alphabetic()) => {
+                    return Err(error(format!("unknown keyword {keyword}")));
+                }
+                _ => {
+                    let color: Vec<f32> = line
+                        .split_whitespace()
+                        .map(str::parse)
+                        .collect::<Result<_, _>>()
+                        .map_err(|_| error(format!("'{line}' is not a color")))?;
+                    let &[red, green, blue] = color.as_slice() else {
+                        return Err(error(format!("expected 3 values per color but got '{line}'")));
+                    };
+                    data.push([red, green, blue]);
+                }
+            }
+        }
+
+        let size = size.ok_or_else(|| LutError::Invalid {
+            line: 0,
+            message: "LUT_3D_SIZE is missing".to_string(),
+        })?;
+        if data.len() != size.pow(3) as usize {
+            return Err(LutError::Invalid {
+                line: 0,
+                message: format!("LUT_3D_SIZE {size} needs {} colors but got {}", size.pow(3), data.len()),
+            });
+        }
+        if (0..3).any(|channel| domain_min[channel] >= domain_max[channel]) {
+            return Err(LutError::Invalid {
+                line: 0,
+                message: "DOMAIN_MIN has to be smaller than DOMAIN_MAX".to_string(),
+            });
+        }
+
+        Ok(Self {
+            title,
+            size,
+            domain_min,
+            domain_max,
+            data,
+        })
+    }
+
+    /// the colors as RGBA texels in the order of the given layout, the values are not clamped
+    fn texels(&self, layout: LutLayout) -> Vec<[f32; 4]> {
+        let size = self.size as usize;
+        let texel = |red: usize, green: usize, blue: usize| {
+            let [red, green, blue] = self.data[red + green * size + blue * size * size];
+            [red, green, blue, 1.]
+        };
+
+        let mut texels = Vec::with_capacity(size.pow(3));
+        match layout {
+            LutLayout::Volume => {
+                for blue in 0..size {
+                    for green in 0..size {
+                        for red in 0..size {
+                            texels.push(texel(red, green, blue));
+                        }
+                    }
+                }
+            }
+            LutLayout::Strip => {
+                for green in 0..size {
+                    for blue in 0..size {
+                        for red in 0..size {
+                            texels.push(texel(red, green, blue));
+                        }
+                    }
+                }
+            }
+        }
+        texels
+    }
+}
+
+/// has to match `LutInfo` in lut.wgsl
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct LutInfo {
+    domain_min: [f32; 3],
+    size: f32,
+    domain_max: [f32; 3],
+    // WebGL needs 16 byte alignment
+    padding: f32,
+}
+
+/// a lookup table on the GPU, bound at group 3 by effects of the `Lut` kind
+pub struct LutTexture {
+    #[allow(unused)]
+    texture: Texture,
+    #[allow(unused)]
+    info_buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+}
+
+impl LutTexture {
+    /// uploads the table as half floats, which keeps the precision of tables with many points and values outside of 0 to 1
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue, lut: &CubeLut, label: &str) -> Result<Self, LutError> {
+        // the strip is `size` slices wide
+        let (width, max_dimension) = match LutLayout::CURRENT {
+            LutLayout::Volume => (lut.size, device.limits().max_texture_dimension_3d),
+            LutLayout::Strip => (lut.size * lut.size, device.limits().max_texture_dimension_2d),
+        };
+        if width > max_dimension {
+            return Err(LutError::TooLarge {
+                size: lut.size,
+                max_dimension,
+            });
+        }
+
+        let size = match LutLayout::CURRENT {
+            LutLayout::Volume => wgpu::Extent3d {
+                width: lut.size,
+                height: lut.size,
+                depth_or_array_layers: lut.size,
+            },
+            LutLayout::Strip => wgpu::Extent3d {
+                width: lut.size * lut.size,
+                height: lut.size,
+                depth_or_array_layers: 1,
+            },
+        };
+        let dimension = match LutLayout::CURRENT {
+            LutLayout::Volume => wgpu::TextureDimension::D3,
+            LutLayout::Strip => wgpu::TextureDimension::D2,
+        };
+        let texels: Vec<half::f16> = lut
+            .texels(LutLayout::CURRENT)
+            .iter()
+            .flatten()
+            .map(|value| half::f16::from_f32(*value))
+            .collect();
+        let texture = Texture::from_data(
+            device,
+            queue,
+            bytemuck::cast_slice(&texels),
+            size,
+            dimension,
+            wgpu::TextureFormat::Rgba16Float,
+            label,
+        );
+
+        let info = LutInfo {
+            domain_min: lut.domain_min,
+            size: lut.size as f32,
+            domain_max: lut.domain_max,
+            padding: 0.,
+        };
+        let info_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("lut info buffer"),
+            contents: bytemuck::bytes_of(&info),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("lut bind group"),
+            layout: &bind_group_layout(device),
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&texture.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&texture.sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: info_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        Ok(Self {
+            texture,
+            info_buffer,
+            bind_group,
+        })
+    }
+
+    pub fn bind_group(&self) -> &wgpu::BindGroup {
+        &self.bind_group
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// a table of the given size where every color is its own index, `red + green * size + blue * size²`
+    fn indexed(size: u32) -> String {
+        let mut source = format!("LUT_3D_SIZE {size}\n");
+        for index in 0..size.pow(3) {
+            source.push_str(&format!("{index} 0 0\n"));
+        }
+        source
+    }
+
+    fn invalid(source: &str) -> (usize, String) {
+        match CubeLut::parse(source) {
+            Err(LutError::Invalid { line, message }) => (line, message),
+            result => panic!("expected an invalid table but got {:?}", result.map(|lut| lut.size)),
+        }
+    }
+
+    #[test]
+    fn keywords_and_colors_are_parsed() {
+        let lut = CubeLut::parse(
+            "# comment\nTITLE \"look\"\nLUT_3D_SIZE 2\nDOMAIN_MIN 0 0.1 0\nDOMAIN_MAX 1 1 2\n\n\
+             0 0 0\n1 0 0\n0 1 0\n1 1 0\n0 0 1\n1 0 1\n0 1 1\n1 1 1.5\n",
+        )
+        .expect("the table is valid");
+        assert_eq!(lut.title.as_deref(), Some("look"));
+        assert_eq!(lut.size, 2);
+        assert_eq!((lut.domain_min, lut.domain_max), ([0., 0.1, 0.], [1., 1., 2.]));
+        assert_eq!(lut.data.len(), 8);
+        assert_eq!(lut.data[7], [1., 1., 1.5]);
+
+        let lut = CubeLut::parse(&format!("LUT_3D_INPUT_RANGE -0.5 1.5\n{}", indexed(2))).expect("the table is valid");
+        assert_eq!((lut.domain_min, lut.domain_max), ([-0.5; 3], [1.5; 3]));
+    }
+
+    #[test]
+    fn the_size_is_required_and_limited() {
+        assert_eq!(invalid("0 0 0"), (0, "LUT_3D_SIZE is missing".to_string()));
+        for size in ["1", "257", "-2", "2.5", "", "many"] {
+            assert_eq!(
+                invalid(&format!("TITLE \"a\"\nLUT_3D_SIZE {size}")),
+                (2, "LUT_3D_SIZE has to be a number from 2 to 256".to_string())
+            );
+        }
+        assert!(matches!(CubeLut::parse("LUT_1D_SIZE 16"), Err(LutError::Unsupported1D)));
+    }
+
+    #[test]
+    fn invalid_domains_are_rejected() {
+        for (domain, line, message) in [
+            ("DOMAIN_MIN 0 0", 1, "expected 3 numbers after DOMAIN_MIN"),
+            ("DOMAIN_MAX 1 1 1 1", 1, "expected 3 numbers after DOMAIN_MAX"),
+            ("DOMAIN_MAX 1 one 1", 1, "expected numbers after DOMAIN_MAX"),
+            ("LUT_3D_INPUT_RANGE 0", 1, "expected 2 numbers after LUT_3D_INPUT_RANGE"),
+            (
+                "DOMAIN_MIN 0 0.5 0\nDOMAIN_MAX 1 0.5 1",
+                0,
+                "DOMAIN_MIN has to be smaller than DOMAIN_MAX",
+            ),
+            ("LUT_3D_INPUT_RANGE 1 0", 0, "DOMAIN_MIN has to be smaller than DOMAIN_MAX"),
+        ] {
+            assert_eq!(invalid(&format!("{domain}\n{}", indexed(2))), (line, message.to_string()));
+        }
+    }
+
+    #[test]
+    fn the_number_of_colors_has_to_match_the_size() {
+        let source = indexed(2);
+        let too_few = source.trim_end().rsplit_once('\n').expect("the table has several lines").0;
+        assert_eq!(invalid(too_few), (0, "LUT_3D_SIZE 2 needs 8 colors but got 7".to_string()));
+        assert_eq!(
+            invalid(&format!("{source}0 0 0\n")),
+            (0, "LUT_3D_SIZE 2 needs 8 colors but got 9".to_string())
+        );
+    }
+
+    #[test]
+    fn invalid_lines_are_rejected_with_their_line() {
+        for (line, message) in [
+            ("0 0", "expected 3 values per color but got '0 0'"),
+            ("0 0 0 1", "expected 3 values per color but got '0 0 0 1'"),
+            ("0 x 0", "'0 x 0' is not a color"),
+            ("LUT_3D_SCALE 2", "unknown keyword LUT_3D_SCALE"),
+        ] {
+            assert_eq!(invalid(&format!("LUT_3D_SIZE 2\n{line}")), (2, message.to_string()));
+        }
+    }
+
+    #[test]
+    fn red_changes_fastest_in_the_volume() {
+        let lut = CubeLut::parse(&indexed(3)).expect("the table is valid");
+        let texels = lut.texels(LutLayout::Volume);
+        // the texels of a 3D texture are ordered by x (red), then y (green), then z (blue)
+        let indices: Vec<f32> = texels.iter().map(|texel| texel[0]).collect();
+        assert_eq!(indices, (0..27).map(|index| index as f32).collect::<Vec<_>>());
+        assert!(texels.iter().all(|texel| texel[3] == 1.));
+    }
+
+    #[test]
+    fn the_strip_has_the_blue_slices_next_to_each_other() {
+        let lut = CubeLut::parse(&indexed(3)).expect("the table is valid");
+        let texels = lut.texels(LutLayout::Strip);
+        assert_eq!(texels.len(), 27);
+        // the strip is 9 pixels wide and 3 high, x is red + blue * size and y is green
+        for green in 0..3 {
+            for blue in 0..3 {
+                for red in 0..3 {
+                    let x = red + blue * 3;
+                    assert_eq!(texels[x + green * 9][0], (red + green * 3 + blue * 9) as f32);
+                }
+            }
+        }
+    }
+}
@@ -1,5 +1,55 @@
-use shader_post_processing_example::run;
+use shader_post_processing_example::config::Config;
+use shader_post_processing_example::{run_with_config, EffectRegistry};
+use std::path::PathBuf;
+
+const USAGE: &str = "\
+shows the post processing effects in a window
+
+usage: shader-post-processing-example [--config <path>]
+
+options:
+    --config <path>      TOML file with the scene image, effects, parameters, sampler and window size
+    --help               print this message";
+
+struct Arguments {
+    config: Option<PathBuf>,
+}
 
 fn main() {
-    pollster::block_on(run());
+    let config = match parse_arguments(std::env::args().skip(1)) {
+        Ok(Some(Arguments { config: Some(path) })) => match Config::load(&path, &EffectRegistry::built_in()) {
+            Ok(config) => config,
+            Err(error) => {
+                eprintln!("error: {}: {error}", path.display());
+                std::process::exit(2);
+            }
+        },
+        Ok(Some(Arguments { config: None })) => Config::default(),
+        Ok(None) => {
+            println!("{USAGE}");
+            return;
+        }
+        Err(error) => {
+            eprintln!("error: {error}\n\n{USAGE}");
+            std::process::exit(2);
+        }
+    };
+
+    if let Err(error) = pollster::block_on(run_with_config(config)) {
+        eprintln!("error: {error}");
+        std::process::exit(1);
+    }
+}
+
+/// returns `None` if the usage was requested
+fn parse_arguments(mut args: impl Iterator<Item = String>) -> Result<Option<Arguments>, String> {
+    let mut config = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--help" | "-h" => return Ok(None),
+            "--config" => config = Some(PathBuf::from(args.next().ok_or("missing value for --config")?)),
+            _ => return Err(format!("unknown argument {arg}")),
+        }
+    }
+    Ok(Some(Arguments { config }))
 }
@@ -1,147 +1,620 @@
+use crate::effect::Effect;
+use crate::effect_parameters::{self, EffectParameters, ParameterLayout};
+use crate::effect_passes::{PassInput, PassLayout};
+use crate::lut::{self, LutTexture};
+use crate::render_target::RenderTarget;
+use crate::shader_globals::Globals;
+use crate::shader_preprocessor;
+
+/// layout of the input texture at group 1, see `RenderTarget::bind_group`
+pub const TEXTURE_BIND_GROUP_LAYOUT: &wgpu::BindGroupLayoutDescriptor = &wgpu::BindGroupLayoutDescriptor {
+    entries: &[
+        wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::FRAGMENT.union(wgpu::ShaderStages::COMPUTE),
+            ty: wgpu::BindingType::Texture {
+                multisampled: false,
+                view_dimension: wgpu::TextureViewDimension::D2,
+                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+            },
+            count: None,
+        },
+        wgpu::BindGroupLayoutEntry {
+            binding: 1,
+            visibility: wgpu::ShaderStages::FRAGMENT.union(wgpu::ShaderStages::COMPUTE),
+            // This should match the filterable field of the
+            // corresponding Texture entry above.
+            ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+            count: None,
+        },
+    ],
+    label: Some("texture_bind_group_layout"),
+};
+
+/// format of the storage textures that compute effects write to, it's supported as storage texture on every backend with compute shaders
+pub const STORAGE_TEXTURE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+/// layout of the output of compute effects at group 3, see `RenderTarget::storage_bind_group`
+pub const STORAGE_BIND_GROUP_LAYOUT: &wgpu::BindGroupLayoutDescriptor = &wgpu::BindGroupLayoutDescriptor {
+    entries: &[wgpu::BindGroupLayoutEntry {
+        binding: 0,
+        visibility: wgpu::ShaderStages::COMPUTE,
+        ty: wgpu::BindingType::StorageTexture {
+            access: wgpu::StorageTextureAccess::WriteOnly,
+            format: STORAGE_TEXTURE_FORMAT,
+            view_dimension: wgpu::TextureViewDimension::D2,
+        },
+        count: None,
+    }],
+    label: Some("storage_bind_group_layout"),
+};
+
+/// compute effects have to declare `@workgroup_size(8, 8)`, every invocation writes one pixel
+const COMPUTE_WORKGROUP_SIZE: u32 = 8;
+
+/// the pipeline that applies an effect, see `PostProcessing::create_pipeline`
+pub enum EffectPipeline {
+    /// draws a fullscreen rectangle with the `vertex` and `fragment` entry points
+    Render(wgpu::RenderPipeline),
+    /// runs the `compute` entry point once per pixel
+    Compute(wgpu::ComputePipeline),
+    /// one render pipeline per pass of a multi pass effect
+    MultiPass(Vec<wgpu::RenderPipeline>),
+}
+
+/// resources that only compute effects need
+struct ComputeOutput {
+    /// the compute shader writes into this storage texture, it's copied to the destination afterwards
+    target: RenderTarget,
+    copy_pipeline: wgpu::RenderPipeline,
+}
+
+/// resources that only multi pass effects need
+struct PassTargets {
+    layout: PassLayout,
+    /// one render target per `@target` of the layout
+    targets: Vec<RenderTarget>,
+}
+
 pub struct PostProcessing {
-    render_pipeline: wgpu::RenderPipeline,
-    texture_bind_group_layout: wgpu::BindGroupLayout,
-    sampler: wgpu::Sampler,
+    pipeline: EffectPipeline,
+    pipeline_layout: wgpu::PipelineLayout,
+    format: wgpu::TextureFormat,
+    parameters: Option<EffectParameters>,
+    /// bound at group 2 by compute and multi pass effects without parameters, because they always bind group 3
+    empty_bind_group: Option<wgpu::BindGroup>,
+    compute_output: Option<ComputeOutput>,
+    pass_targets: Option<PassTargets>,
+    /// the lookup table of effects of the `Lut` kind, bound at group 3
+    lut: Option<LutTexture>,
 }
 
 impl PostProcessing {
-    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat, globals_bind_group_layout: &wgpu::BindGroupLayout, shader: wgpu::ShaderModule) -> Self {
-        let texture_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            entries: &[
-                wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Texture {
-                        multisampled: false,
-                        view_dimension: wgpu::TextureViewDimension::D2,
-                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
-                    },
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 1,
-                    visibility: wgpu::ShaderStages::FRAGMENT,
-                    // This should match the filterable field of the
-                    // corresponding Texture entry above.
-                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
-                    count: None,
-                },
-            ],
-            label: Some("texture_bind_group_layout"),
+    pub fn new(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        globals_bind_group_layout: &wgpu::BindGroupLayout,
+        shader: wgpu::ShaderModule,
+    ) -> Self {
+        Self::create(device, format, globals_bind_group_layout, shader, None)
+    }
+
+    /// creates an effect with its own uniform parameters, the shader has to declare them at `@group(2) @binding(0)`
+    /// as generated by `ParameterLayout::wgsl_declarations`
+    pub fn with_parameters(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        globals_bind_group_layout: &wgpu::BindGroupLayout,
+        shader: wgpu::ShaderModule,
+        parameters: ParameterLayout,
+    ) -> Self {
+        Self::create(device, format, globals_bind_group_layout, shader, Some(parameters))
+    }
+
+    fn create(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        globals_bind_group_layout: &wgpu::BindGroupLayout,
+        shader: wgpu::ShaderModule,
+        parameters: Option<ParameterLayout>,
+    ) -> Self {
+        let texture_bind_group_layout = device.create_bind_group_layout(TEXTURE_BIND_GROUP_LAYOUT);
+
+        let parameters_bind_group_layout = device.create_bind_group_layout(effect_parameters::BIND_GROUP_LAYOUT);
+        let parameters = parameters.map(|parameters| EffectParameters::new(device, &parameters_bind_group_layout, parameters));
+
+        let mut bind_group_layouts = vec![globals_bind_group_layout, &texture_bind_group_layout];
+        if parameters.is_some() {
+            bind_group_layouts.push(&parameters_bind_group_layout);
+        }
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("post processing render pipeline layout"),
+            bind_group_layouts: &bind_group_layouts,
+            push_constant_ranges: &[],
         });
 
-        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
-            address_mode_u: wgpu::AddressMode::ClampToEdge,
-            address_mode_v: wgpu::AddressMode::ClampToEdge,
-            address_mode_w: wgpu::AddressMode::ClampToEdge,
-            mag_filter: wgpu::FilterMode::Linear,
-            min_filter: wgpu::FilterMode::Nearest,
-            mipmap_filter: wgpu::FilterMode::Nearest,
-            ..Default::default()
+        let render_pipeline = create_render_pipeline(
+            device,
+            &pipeline_layout,
+            format,
+            &shader,
+            "fragment",
+            wgpu::BlendState::ALPHA_BLENDING,
+        );
+
+        Self {
+            pipeline: EffectPipeline::Render(render_pipeline),
+            pipeline_layout,
+            format,
+            parameters,
+            empty_bind_group: None,
+            compute_output: None,
+            pass_targets: None,
+            lut: None,
+        }
+    }
+
+    /// creates an effect that runs the `compute` entry point of the shader, check `is_compute_supported` first.
+    /// The output of the compute shader has the given size and has to be resized together with the other render targets.
+    pub fn compute(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        size: wgpu::Extent3d,
+        globals_bind_group_layout: &wgpu::BindGroupLayout,
+        shader: wgpu::ShaderModule,
+        parameters: Option<ParameterLayout>,
+    ) -> Self {
+        let texture_bind_group_layout = device.create_bind_group_layout(TEXTURE_BIND_GROUP_LAYOUT);
+        let storage_bind_group_layout = device.create_bind_group_layout(STORAGE_BIND_GROUP_LAYOUT);
+        let (parameters_bind_group_layout, parameters, empty_bind_group) = create_parameters_at_group_2(device, parameters);
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("post processing compute pipeline layout"),
+            bind_group_layouts: &[
+                globals_bind_group_layout,
+                &texture_bind_group_layout,
+                &parameters_bind_group_layout,
+                &storage_bind_group_layout,
+            ],
+            push_constant_ranges: &[],
         });
+        let compute_pipeline = create_compute_pipeline(device, &pipeline_layout, &shader);
 
-        let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-            label: Some("post processing render pipeline layout"),
+        // the storage texture can't have the format of the destination, because sRGB formats can't be used as storage
+        let copy_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("post processing copy pipeline layout"),
             bind_group_layouts: &[globals_bind_group_layout, &texture_bind_group_layout],
             push_constant_ranges: &[],
         });
+        let copy_shader = shader_preprocessor::create_embedded_shader_module(device, "copy.wgsl");
+        let copy_pipeline = create_render_pipeline(
+            device,
+            &copy_pipeline_layout,
+            format,
+            &copy_shader,
+            "fragment",
+            wgpu::BlendState::ALPHA_BLENDING,
+        );
 
-        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("post processing render pipeline"),
-            layout: Some(&render_pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &shader,
-                entry_point: "vertex",
-                buffers: &[],
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &shader,
-                entry_point: "fragment",
-                targets: &[Some(wgpu::ColorTargetState {
-                    format,
-                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
+        Self {
+            pipeline: EffectPipeline::Compute(compute_pipeline),
+            pipeline_layout,
+            format,
+            parameters,
+            empty_bind_group,
+            compute_output: Some(ComputeOutput {
+                target: RenderTarget::storage(device, size, "compute effect output"),
+                copy_pipeline,
             }),
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList,
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Ccw,
-                cull_mode: Some(wgpu::Face::Back),
-                // Setting this to anything other than Fill requires Features::NON_FILL_POLYGON_MODE
-                polygon_mode: wgpu::PolygonMode::Fill,
-                // Requires Features::DEPTH_CLIP_CONTROL
-                unclipped_depth: false,
-                // Requires Features::CONSERVATIVE_RASTERIZATION
-                conservative: false,
-            },
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState {
-                count: 1,
-                mask: !0,
-                alpha_to_coverage_enabled: false,
-            },
-            multiview: None,
-            cache: None,
+            pass_targets: None,
+            lut: None,
+        }
+    }
+
+    /// creates an effect that renders the passes of the layout one after another into its own render targets,
+    /// see `effect_passes`. Like the output of compute effects, the targets have to be resized together with the other render targets.
+    pub fn multi_pass(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        size: wgpu::Extent3d,
+        globals_bind_group_layout: &wgpu::BindGroupLayout,
+        shader: wgpu::ShaderModule,
+        parameters: Option<ParameterLayout>,
+        passes: PassLayout,
+    ) -> Self {
+        let texture_bind_group_layout = device.create_bind_group_layout(TEXTURE_BIND_GROUP_LAYOUT);
+        let (parameters_bind_group_layout, parameters, empty_bind_group) = create_parameters_at_group_2(device, parameters);
+
+        // the input of the effect is bound at group 3 with the same layout as the input of the pass
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("post processing multi pass pipeline layout"),
+            bind_group_layouts: &[
+                globals_bind_group_layout,
+                &texture_bind_group_layout,
+                &parameters_bind_group_layout,
+                &texture_bind_group_layout,
+            ],
+            push_constant_ranges: &[],
+        });
+        let pipelines = create_pass_pipelines(device, &pipeline_layout, format, &shader, &passes);
+
+        Self {
+            pipeline: EffectPipeline::MultiPass(pipelines),
+            pipeline_layout,
+            format,
+            parameters,
+            empty_bind_group,
+            compute_output: None,
+            pass_targets: Some(PassTargets {
+                targets: create_pass_targets(device, size, format, &passes),
+                layout: passes,
+            }),
+            lut: None,
+        }
+    }
+
+    /// creates an effect that grades colors with the lookup table, the shader has to include `lut.wgsl`
+    pub fn with_lut(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        globals_bind_group_layout: &wgpu::BindGroupLayout,
+        shader: wgpu::ShaderModule,
+        parameters: Option<ParameterLayout>,
+        lut: LutTexture,
+    ) -> Self {
+        let texture_bind_group_layout = device.create_bind_group_layout(TEXTURE_BIND_GROUP_LAYOUT);
+        let (parameters_bind_group_layout, parameters, empty_bind_group) = create_parameters_at_group_2(device, parameters);
+        let lut_bind_group_layout = lut::bind_group_layout(device);
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("post processing lut pipeline layout"),
+            bind_group_layouts: &[
+                globals_bind_group_layout,
+                &texture_bind_group_layout,
+                &parameters_bind_group_layout,
+                &lut_bind_group_layout,
+            ],
+            push_constant_ranges: &[],
         });
+        let render_pipeline = create_render_pipeline(
+            device,
+            &pipeline_layout,
+            format,
+            &shader,
+            "fragment",
+            wgpu::BlendState::ALPHA_BLENDING,
+        );
 
         Self {
-            render_pipeline,
-            texture_bind_group_layout,
-            sampler,
+            pipeline: EffectPipeline::Render(render_pipeline),
+            pipeline_layout,
+            format,
+            parameters,
+            empty_bind_group,
+            compute_output: None,
+            pass_targets: None,
+            lut: Some(lut),
+        }
+    }
+
+    /// compute shaders are not available on every backend, e.g. not on WebGL
+    pub fn is_compute_supported(device: &wgpu::Device) -> bool {
+        device.limits().max_compute_workgroups_per_dimension > 0 && device.limits().max_storage_textures_per_shader_stage > 0
+    }
+
+    /// replaces the lookup table of effects of the `Lut` kind, returns false if the effect doesn't use a table
+    pub fn set_lut(&mut self, lut: LutTexture) -> bool {
+        match &mut self.lut {
+            Some(current) => {
+                *current = lut;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// the passes of multi pass effects
+    pub fn passes(&self) -> Option<&PassLayout> {
+        self.pass_targets.as_ref().map(|pass_targets| &pass_targets.layout)
+    }
+
+    /// creates a pipeline of the same kind for a different shader that is compatible with this effect
+    pub fn create_pipeline(&self, device: &wgpu::Device, shader: &wgpu::ShaderModule) -> EffectPipeline {
+        match (&self.pipeline, &self.pass_targets) {
+            (EffectPipeline::Render(_), _) => EffectPipeline::Render(create_render_pipeline(
+                device,
+                &self.pipeline_layout,
+                self.format,
+                shader,
+                "fragment",
+                wgpu::BlendState::ALPHA_BLENDING,
+            )),
+            (EffectPipeline::Compute(_), _) => EffectPipeline::Compute(create_compute_pipeline(device, &self.pipeline_layout, shader)),
+            (EffectPipeline::MultiPass(_), Some(pass_targets)) => EffectPipeline::MultiPass(create_pass_pipelines(
+                device,
+                &self.pipeline_layout,
+                self.format,
+                shader,
+                &pass_targets.layout,
+            )),
+            (EffectPipeline::MultiPass(_), None) => unreachable!("multi pass effects are created with targets"),
+        }
+    }
+
+    /// replaces the shader of the effect with a pipeline from `create_pipeline`
+    pub fn set_pipeline(&mut self, pipeline: EffectPipeline) {
+        self.pipeline = pipeline;
+    }
+}
+
+impl Effect for PostProcessing {
+    /// recreates the output of compute effects and the targets of multi pass effects if the size of the render targets changed
+    fn resize(&mut self, device: &wgpu::Device, size: wgpu::Extent3d) {
+        if let Some(compute_output) = &mut self.compute_output {
+            if !compute_output.target.matches(size, STORAGE_TEXTURE_FORMAT) {
+                compute_output.target = RenderTarget::storage(device, size, "compute effect output");
+            }
+        }
+        let format = self.format;
+        if let Some(pass_targets) = &mut self.pass_targets {
+            let outdated = pass_targets
+                .layout
+                .targets
+                .iter()
+                .zip(&pass_targets.targets)
+                .any(|(info, target)| !target.matches(info.size(size), format));
+            if outdated {
+                pass_targets.targets = create_pass_targets(device, size, format, &pass_targets.layout);
+            }
+        }
+    }
+
+    /// uploads the changed parameters
+    fn update(&mut self, queue: &wgpu::Queue, _globals: &Globals) {
+        if let Some(parameters) = &mut self.parameters {
+            parameters.upload(queue);
         }
     }
 
-    /// copies the the texture from the src_view and applies the
+    /// copies the the texture from the src bind group and applies the
     /// post processing shader effect before pushing it to the dst_view
-    pub fn render_pass(
+    fn encode(
         &self,
-        device: &wgpu::Device,
         encoder: &mut wgpu::CommandEncoder,
-        src_view: &wgpu::TextureView,
+        src_bind_group: &wgpu::BindGroup,
         dst_view: &wgpu::TextureView,
         globals_bind_group: &wgpu::BindGroup,
-    ) -> Result<(), wgpu::SurfaceError> {
-        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-            label: Some("post processing render pass"),
-            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                view: dst_view,
-                resolve_target: None,
-                ops: wgpu::Operations {
-                    load: wgpu::LoadOp::Load,
-                    store: wgpu::StoreOp::Store,
-                },
-            })],
-            depth_stencil_attachment: None,
-            occlusion_query_set: None,
-            timestamp_writes: None,
-        });
+    ) {
+        let parameters_bind_group = self.parameters.as_ref().map(EffectParameters::bind_group);
+        match (&self.pipeline, &self.compute_output, &self.pass_targets) {
+            (EffectPipeline::Compute(compute_pipeline), Some(compute_output), _) => {
+                let storage_bind_group = compute_output
+                    .target
+                    .storage_bind_group()
+                    .expect("compute output is a storage target");
+                let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                    label: Some("post processing compute pass"),
+                    timestamp_writes: None,
+                });
+                compute_pass.set_pipeline(compute_pipeline);
+                compute_pass.set_bind_group(0, globals_bind_group, &[]);
+                compute_pass.set_bind_group(1, src_bind_group, &[]);
+                let parameters_bind_group = parameters_bind_group.or(self.empty_bind_group.as_ref());
+                compute_pass.set_bind_group(2, parameters_bind_group.expect("parameters or empty bind group"), &[]);
+                compute_pass.set_bind_group(3, storage_bind_group, &[]);
+                let size = compute_output.target.size();
+                compute_pass.dispatch_workgroups(
+                    size.width.div_ceil(COMPUTE_WORKGROUP_SIZE),
+                    size.height.div_ceil(COMPUTE_WORKGROUP_SIZE),
+                    1,
+                );
+                drop(compute_pass);
+
+                draw_fullscreen(
+                    encoder,
+                    &compute_output.copy_pipeline,
+                    dst_view,
+                    &[globals_bind_group, compute_output.target.bind_group()],
+                );
+            }
+            (EffectPipeline::MultiPass(pipelines), _, Some(pass_targets)) => {
+                let parameters_bind_group = parameters_bind_group
+                    .or(self.empty_bind_group.as_ref())
+                    .expect("parameters or empty bind group");
+                for (pass, pipeline) in pass_targets.layout.passes.iter().zip(pipelines) {
+                    let input_bind_group = match pass.input {
+                        PassInput::Source => src_bind_group,
+                        PassInput::Target(target_index) => pass_targets.targets[target_index].bind_group(),
+                    };
+                    let output_view = match pass.output {
+                        Some(target_index) => pass_targets.targets[target_index].view(),
+                        None => dst_view,
+                    };
+                    draw_fullscreen(
+                        encoder,
+                        pipeline,
+                        output_view,
+                        &[globals_bind_group, input_bind_group, parameters_bind_group, src_bind_group],
+                    );
+                }
+            }
+            (EffectPipeline::Render(render_pipeline), _, _) => {
+                let mut bind_groups = vec![globals_bind_group, src_bind_group];
+                bind_groups.extend(parameters_bind_group.or(self.empty_bind_group.as_ref()));
+                bind_groups.extend(self.lut.as_ref().map(LutTexture::bind_group));
+                draw_fullscreen(encoder, render_pipeline, dst_view, &bind_groups);
+            }
+            (EffectPipeline::Compute(_), None, _) => unreachable!("compute effects are created with an output"),
+            (EffectPipeline::MultiPass(_), _, None) => unreachable!("multi pass effects are created with targets"),
+        }
+    }
+
+    fn parameters(&self) -> Option<&EffectParameters> {
+        self.parameters.as_ref()
+    }
+
+    fn parameters_mut(&mut self) -> Option<&mut EffectParameters> {
+        self.parameters.as_mut()
+    }
 
-        render_pass.set_pipeline(&self.render_pipeline);
-        render_pass.set_bind_group(0, globals_bind_group, &[]);
-        // technically, creating a new bind group for every frame is wasteful but for demonstration purposes it is fine
-        render_pass.set_bind_group(1, &self.create_texture_bind_group(device, src_view), &[]);
-        render_pass.draw(0..6, 0..1);
+    fn as_post_processing_mut(&mut self) -> Option<&mut PostProcessing> {
+        Some(self)
+    }
+}
 
-        Ok(())
+/// draws the fullscreen rectangle of `prelude.wgsl` with the bind groups starting at group 0
+fn draw_fullscreen(
+    encoder: &mut wgpu::CommandEncoder,
+    render_pipeline: &wgpu::RenderPipeline,
+    dst_view: &wgpu::TextureView,
+    bind_groups: &[&wgpu::BindGroup],
+) {
+    let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some("post processing render pass"),
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+            view: dst_view,
+            resolve_target: None,
+            ops: wgpu::Operations {
+                load: wgpu::LoadOp::Load,
+                store: wgpu::StoreOp::Store,
+            },
+        })],
+        depth_stencil_attachment: None,
+        occlusion_query_set: None,
+        timestamp_writes: None,
+    });
+
+    render_pass.set_pipeline(render_pipeline);
+    for (index, bind_group) in bind_groups.iter().enumerate() {
+        render_pass.set_bind_group(index as u32, bind_group, &[]);
     }
+    render_pass.draw(0..6, 0..1);
+}
 
-    fn create_texture_bind_group(&self, device: &wgpu::Device, view: &wgpu::TextureView) -> wgpu::BindGroup {
+/// the layout of group 2 for effects that always bind group 3: the parameters if there are any, otherwise an empty bind group
+fn create_parameters_at_group_2(
+    device: &wgpu::Device,
+    parameters: Option<ParameterLayout>,
+) -> (wgpu::BindGroupLayout, Option<EffectParameters>, Option<wgpu::BindGroup>) {
+    let bind_group_layout = match parameters {
+        Some(_) => device.create_bind_group_layout(effect_parameters::BIND_GROUP_LAYOUT),
+        None => device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("empty bind group layout"),
+            entries: &[],
+        }),
+    };
+    let empty_bind_group = parameters.is_none().then(|| {
         device.create_bind_group(&wgpu::BindGroupDescriptor {
-            layout: &self.texture_bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: wgpu::BindingResource::TextureView(view),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: wgpu::BindingResource::Sampler(&self.sampler),
+            label: Some("empty bind group"),
+            layout: &bind_group_layout,
+            entries: &[],
+        })
+    });
+    let parameters = parameters.map(|parameters| EffectParameters::new(device, &bind_group_layout, parameters));
+    (bind_group_layout, parameters, empty_bind_group)
+}
+
+fn create_pass_targets(device: &wgpu::Device, size: wgpu::Extent3d, format: wgpu::TextureFormat, passes: &PassLayout) -> Vec<RenderTarget> {
+    passes
+        .targets
+        .iter()
+        .map(|target| RenderTarget::new(device, target.size(size), format, &target.name))
+        .collect()
+}
+
+/// the passes that write to their own targets replace the content or add to it, the last pass blends like every other effect
+fn create_pass_pipelines(
+    device: &wgpu::Device,
+    pipeline_layout: &wgpu::PipelineLayout,
+    format: wgpu::TextureFormat,
+    shader: &wgpu::ShaderModule,
+    passes: &PassLayout,
+) -> Vec<wgpu::RenderPipeline> {
+    const ADDITIVE: wgpu::BlendComponent = wgpu::BlendComponent {
+        src_factor: wgpu::BlendFactor::One,
+        dst_factor: wgpu::BlendFactor::One,
+        operation: wgpu::BlendOperation::Add,
+    };
+
+    passes
+        .passes
+        .iter()
+        .map(|pass| {
+            let blend = match (pass.additive, pass.output) {
+                (true, _) => wgpu::BlendState {
+                    color: ADDITIVE,
+                    alpha: ADDITIVE,
                 },
-            ],
-            label: Some("diffuse_bind_group"),
+                (false, Some(_)) => wgpu::BlendState::REPLACE,
+                (false, None) => wgpu::BlendState::ALPHA_BLENDING,
+            };
+            create_render_pipeline(device, pipeline_layout, format, shader, &pass.entry_point, blend)
         })
-    }
+        .collect()
+}
+
+fn create_compute_pipeline(
+    device: &wgpu::Device,
+    pipeline_layout: &wgpu::PipelineLayout,
+    shader: &wgpu::ShaderModule,
+) -> wgpu::ComputePipeline {
+    device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+        label: Some("post processing compute pipeline"),
+        layout: Some(pipeline_layout),
+        module: shader,
+        entry_point: "compute",
+        compilation_options: wgpu::PipelineCompilationOptions::default(),
+        cache: None,
+    })
+}
+
+fn create_render_pipeline(
+    device: &wgpu::Device,
+    render_pipeline_layout: &wgpu::PipelineLayout,
+    format: wgpu::TextureFormat,
+    shader: &wgpu::ShaderModule,
+    fragment_entry_point: &str,
+    blend: wgpu::BlendState,
+) -> wgpu::RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("post processing render pipeline"),
+        layout: Some(render_pipeline_layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: "vertex",
+            buffers: &[],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: fragment_entry_point,
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: Some(blend),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: Some(wgpu::Face::Back),
+            // Setting this to anything other than Fill requires Features::NON_FILL_POLYGON_MODE
+            polygon_mode: wgpu::PolygonMode::Fill,
+            // Requires Features::DEPTH_CLIP_CONTROL
+            unclipped_depth: false,
+            // Requires Features::CONSERVATIVE_RASTERIZATION
+            conservative: false,
+        },
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState {
+            count: 1,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+        multiview: None,
+        cache: None,
+    })
 }
@@ -0,0 +1,68 @@
+use crate::effect::Effect;
+use crate::render_target::RenderTarget;
+
+/// an ordered list of post processing effects that are applied one after another
+///
+/// every pass reads the output of the previous pass. Intermediate results are written
+/// into two render targets that are used alternately (ping-pong), only the last pass writes to the final destination.
+pub struct PostProcessingChain {
+    effect_indices: Vec<usize>,
+    ping_pong: [RenderTarget; 2],
+}
+
+impl PostProcessingChain {
+    /// `size` and `format` describe the final destination and are used for the intermediate render targets
+    pub fn new(device: &wgpu::Device, effect_indices: Vec<usize>, size: wgpu::Extent3d, format: wgpu::TextureFormat) -> Self {
+        Self {
+            effect_indices,
+            ping_pong: create_ping_pong(device, size, format),
+        }
+    }
+
+    pub fn effect_indices(&self) -> &[usize] {
+        &self.effect_indices
+    }
+
+    pub fn set_effect_indices(&mut self, effect_indices: Vec<usize>) {
+        self.effect_indices = effect_indices;
+    }
+
+    /// recreates the intermediate render targets if the size or format of the final destination changed
+    pub fn resize(&mut self, device: &wgpu::Device, size: wgpu::Extent3d, format: wgpu::TextureFormat) {
+        if !self.ping_pong[0].matches(size, format) {
+            self.ping_pong = create_ping_pong(device, size, format);
+        }
+    }
+
+    /// applies every effect of the chain in order, starting with the texture of the src bind group and ending in the dst_view
+    pub fn render_pass(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        effects: &[Option<Box<dyn Effect>>],
+        src_bind_group: &wgpu::BindGroup,
+        dst_view: &wgpu::TextureView,
+        globals_bind_group: &wgpu::BindGroup,
+    ) {
+        let last = self.effect_indices.len().saturating_sub(1);
+        let mut current_src = src_bind_group;
+        for (pass, &effect_index) in self.effect_indices.iter().enumerate() {
+            let effect = effects[effect_index]
+                .as_deref()
+                .expect("the effects of the chain are created when it is set");
+            if pass == last {
+                effect.encode(encoder, current_src, dst_view, globals_bind_group);
+            } else {
+                let current_dst = &self.ping_pong[pass % 2];
+                effect.encode(encoder, current_src, current_dst.view(), globals_bind_group);
+                current_src = current_dst.bind_group();
+            }
+        }
+    }
+}
+
+fn create_ping_pong(device: &wgpu::Device, size: wgpu::Extent3d, format: wgpu::TextureFormat) -> [RenderTarget; 2] {
+    [
+        RenderTarget::new(device, size, format, "ping pong texture 0"),
+        RenderTarget::new(device, size, format, "ping pong texture 1"),
+    ]
+}
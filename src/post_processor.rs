@@ -0,0 +1,233 @@
+//! applies the post processing effects in a wgpu renderer that owns the device, the event loop and the frame
+//!
+//! the renderer draws its frame into a texture and passes the view of it as input. Every frame it changes the globals,
+//! e.g. the time and cursor, calls `update` and records the effects into its own command encoder with `encode`.
+
+use crate::effect::Effect;
+use crate::effect_registry::{EffectDescriptor, EffectRegistry};
+use crate::post_processing_chain::PostProcessingChain;
+use crate::render_target;
+use crate::shader_globals::{self, Globals};
+use wgpu::util::DeviceExt;
+
+/// the effect index is not below `PostProcessor::effect_count`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct UnknownEffect(pub usize);
+
+impl std::fmt::Display for UnknownEffect {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "there is no effect with the index {}", self.0)
+    }
+}
+
+impl std::error::Error for UnknownEffect {}
+
+pub struct PostProcessor {
+    format: wgpu::TextureFormat,
+    size: wgpu::Extent3d,
+    globals: Globals,
+    globals_buffer: wgpu::Buffer,
+    globals_bind_group_layout: wgpu::BindGroupLayout,
+    globals_bind_group: wgpu::BindGroup,
+    input_bind_group: wgpu::BindGroup,
+    effect_registry: EffectRegistry,
+    /// one per registered effect, the shader effects are created when they are first used, see `create_effect`
+    effects: Vec<Option<Box<dyn Effect>>>,
+    chain: PostProcessingChain,
+}
+
+impl PostProcessor {
+    /// registers the built in effects for output views of the given format, they are created when they enter the chain.
+    /// The input texture has the given size and needs `TEXTURE_BINDING` usage, the output views are expected to have the same size.
+    /// The chain starts empty, see `set_chain`.
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat, input_view: &wgpu::TextureView, size: wgpu::Extent3d) -> Self {
+        let mut globals = Globals::new();
+        globals.set_resolution(size.width, size.height);
+
+        let globals_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("globals buffer"),
+            contents: bytemuck::bytes_of(&globals),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let globals_bind_group_layout = device.create_bind_group_layout(shader_globals::BIND_GROUP_LAYOUT);
+
+        let globals_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("globals bind group"),
+            layout: &globals_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: globals_buffer.as_entire_binding(),
+            }],
+        });
+
+        let effect_registry = EffectRegistry::built_in();
+        let effects = effect_registry.names().map(|_| None).collect();
+
+        Self {
+            format,
+            size,
+            globals,
+            globals_buffer,
+            globals_bind_group_layout,
+            globals_bind_group,
+            input_bind_group: render_target::create_input_bind_group(device, input_view),
+            effect_registry,
+            effects,
+            chain: PostProcessingChain::new(device, Vec::new(), size, format),
+        }
+    }
+
+    /// replaces the input, e.g. after the renderer recreated its frame texture with a new size.
+    /// The render targets of the chain and the effects are recreated if the size changed.
+    pub fn set_input(&mut self, device: &wgpu::Device, input_view: &wgpu::TextureView, size: wgpu::Extent3d) {
+        self.input_bind_group = render_target::create_input_bind_group(device, input_view);
+        if size != self.size {
+            self.size = size;
+            self.chain.resize(device, size, self.format);
+            for effect in self.effects.iter_mut().flatten() {
+                effect.resize(device, size);
+            }
+            self.globals.set_resolution(size.width, size.height);
+        }
+    }
+
+    pub fn format(&self) -> wgpu::TextureFormat {
+        self.format
+    }
+
+    pub fn size(&self) -> wgpu::Extent3d {
+        self.size
+    }
+
+    /// the values of the next `update`, the resolution is set by `set_input`
+    pub fn globals(&self) -> &Globals {
+        &self.globals
+    }
+
+    pub fn globals_mut(&mut self) -> &mut Globals {
+        &mut self.globals
+    }
+
+    /// the layout of the globals at group 0, e.g. for scenes that are drawn with the same globals
+    pub fn globals_bind_group_layout(&self) -> &wgpu::BindGroupLayout {
+        &self.globals_bind_group_layout
+    }
+
+    pub fn globals_bind_group(&self) -> &wgpu::BindGroup {
+        &self.globals_bind_group
+    }
+
+    /// names of the available effects, the position of a name is its effect index
+    pub fn effect_names(&self) -> impl Iterator<Item = &str> {
+        self.effect_registry.names()
+    }
+
+    pub fn effect_index(&self, name: &str) -> Option<usize> {
+        self.effect_registry.index_of(name)
+    }
+
+    pub fn effect_name(&self, effect_index: usize) -> Option<&str> {
+        self.effect_registry.name(effect_index)
+    }
+
+    /// number of registered effects, valid effect indices are `0..effect_count()`
+    pub fn effect_count(&self) -> usize {
+        self.effects.len()
+    }
+
+    /// `None` if the index is unknown or the effect was not created yet, the effects of the chain are always created
+    pub fn effect(&self, effect_index: usize) -> Option<&dyn Effect> {
+        self.effects.get(effect_index)?.as_deref()
+    }
+
+    pub fn effect_mut(&mut self, effect_index: usize) -> Option<&mut (dyn Effect + 'static)> {
+        self.effects.get_mut(effect_index)?.as_deref_mut()
+    }
+
+    /// returns the effect and creates it first if it wasn't used yet, e.g. to change its parameters before it enters the chain
+    pub fn create_effect(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        effect_index: usize,
+    ) -> Result<&mut dyn Effect, UnknownEffect> {
+        let (effect_registry, format, size) = (&self.effect_registry, self.format, self.size);
+        let globals_bind_group_layout = &self.globals_bind_group_layout;
+        let effect = self.effects.get_mut(effect_index).ok_or(UnknownEffect(effect_index))?;
+        let effect = effect.get_or_insert_with(|| {
+            let descriptor = effect_registry
+                .descriptor(effect_index)
+                .expect("effects without a descriptor are added with add_effect");
+            Box::new(descriptor.create(device, queue, format, size, globals_bind_group_layout))
+        });
+        Ok(effect.as_mut())
+    }
+
+    /// the indices of the effects that are applied in order
+    pub fn chain(&self) -> &[usize] {
+        self.chain.effect_indices()
+    }
+
+    /// changes the applied effects and creates the ones that weren't used yet, an empty chain doesn't record anything in `encode`.
+    /// The chain is kept if an index is unknown.
+    pub fn set_chain(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, effect_indices: Vec<usize>) -> Result<(), UnknownEffect> {
+        if let Some(&effect_index) = effect_indices.iter().find(|&&effect_index| effect_index >= self.effects.len()) {
+            return Err(UnknownEffect(effect_index));
+        }
+        for &effect_index in &effect_indices {
+            self.create_effect(device, queue, effect_index)?;
+        }
+        self.chain.set_effect_indices(effect_indices);
+        Ok(())
+    }
+
+    /// uploads the globals and the changed parameters of the effects, call it once per frame before `encode`.
+    /// Advances the frame number and seed of the globals for the next frame afterwards.
+    pub fn update(&mut self, queue: &wgpu::Queue) {
+        queue.write_buffer(&self.globals_buffer, 0, bytemuck::bytes_of(&self.globals));
+        for effect in self.effects.iter_mut().flatten() {
+            effect.update(queue, &self.globals);
+        }
+        self.globals.next_frame();
+    }
+
+    /// records the effects of the chain, which read the input and write the final result to the dst_view
+    pub fn encode(&self, encoder: &mut wgpu::CommandEncoder, dst_view: &wgpu::TextureView) {
+        self.chain
+            .render_pass(encoder, &self.effects, &self.input_bind_group, dst_view, &self.globals_bind_group);
+    }
+
+    /// adds an effect that implements `Effect` in Rust and returns its effect index.
+    /// The effect is created for the format and size of the post processor and is resized with it.
+    /// It replaces an effect with the same name and keeps its index.
+    pub fn add_effect(&mut self, name: &str, effect: Box<dyn Effect>) -> usize {
+        let effect_index = self.effect_registry.register_name(name);
+        self.insert_effect(effect_index, effect)
+    }
+
+    /// the registered effects, e.g. to parse configs and presets that use effects which were added at runtime
+    pub fn effect_registry(&self) -> &EffectRegistry {
+        &self.effect_registry
+    }
+
+    /// every registered effect, `None` for effects that were not created yet
+    pub(crate) fn effects_mut(&mut self) -> &mut [Option<Box<dyn Effect>>] {
+        &mut self.effects
+    }
+
+    /// registers the effect that was created from the descriptor, it replaces an effect with the same name and keeps its index
+    pub(crate) fn register(&mut self, descriptor: EffectDescriptor, effect: Box<dyn Effect>) -> usize {
+        let effect_index = self.effect_registry.register(descriptor);
+        self.insert_effect(effect_index, effect)
+    }
+
+    fn insert_effect(&mut self, effect_index: usize, effect: Box<dyn Effect>) -> usize {
+        if effect_index < self.effects.len() {
+            self.effects[effect_index] = Some(effect);
+        } else {
+            self.effects.push(Some(effect));
+        }
+        effect_index
+    }
+}
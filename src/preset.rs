@@ -0,0 +1,256 @@
+//! named snapshots of the active effects and their parameter values, optionally with a cursor position and a time
+//!
+//! presets are stored in the TOML format of the config files, see `config`:
+//!
+//! ```toml
+//! name = "underwater"
+//! effects = ["wave_distortion", "droplet"]
+//! # the cursor position from -1 to 1, (1,0) applies the effects to the whole image
+//! cursor = [1, 0]
+//! # stops the animations at the given time in seconds
+//! time = 1.5
+//!
+//! [parameters.droplet]
+//! fall_off_distance = 0.4
+//! ```
+//!
+//! Parameters that are not listed keep their default values, so a preset always shows the same look.
+
+use crate::config::{self, ConfigError, Number, Numbers, ParameterTables, ParameterValue};
+use crate::effect_registry::EffectRegistry;
+use crate::post_processor::PostProcessor;
+use serde::Deserialize;
+use toml::Spanned;
+
+/// the sources of the presets that are embedded in the binary, e.g. for the web build
+const BUILT_IN: &[&str] = &[
+    include_str!("presets/underwater.toml"),
+    include_str!("presets/retro_tv.toml"),
+    include_str!("presets/comic.toml"),
+];
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Preset {
+    pub name: String,
+    /// the names of the effects that are applied in order
+    pub effects: Vec<String>,
+    pub parameters: Vec<ParameterValue>,
+    pub cursor: Option<(f32, f32)>,
+    pub time: Option<f32>,
+}
+
+impl Preset {
+    pub fn built_in() -> Vec<Self> {
+        let effect_registry = EffectRegistry::built_in();
+        BUILT_IN
+            .iter()
+            .map(|source| Self::parse(source, &effect_registry).expect("the built-in presets are valid"))
+            .collect()
+    }
+
+    /// the active effects of the post processor with all their parameter values and the cursor position of its globals.
+    /// The time is left out so the animations keep running when the preset is applied.
+    pub fn capture(name: impl Into<String>, post_processor: &PostProcessor) -> Self {
+        let mut preset = Preset {
+            name: name.into(),
+            cursor: Some((post_processor.globals().cursor_x, post_processor.globals().cursor_y)),
+            ..Default::default()
+        };
+        for &effect_index in post_processor.chain() {
+            let effect = post_processor
+                .effect_name(effect_index)
+                .expect("the chain only contains registered effects");
+            // an effect that is applied several times has the same parameters every time
+            let captured = preset.effects.iter().any(|captured| captured == effect);
+            preset.effects.push(effect.to_string());
+            let Some(parameters) = post_processor
+                .effect(effect_index)
+                .and_then(|effect| effect.parameters())
+                .filter(|_| !captured)
+            else {
+                continue;
+            };
+            for parameter in &parameters.layout().parameters {
+                preset.parameters.push(ParameterValue {
+                    effect: effect.to_string(),
+                    name: parameter.name.clone(),
+                    values: parameters.get(&parameter.name).expect("the parameter is part of the layout"),
+                });
+            }
+        }
+        preset
+    }
+
+    /// the effects and parameters have to be registered, e.g. `PostProcessor::effect_registry`.
+    /// Errors name the line and the offending key.
+    pub fn parse(source: &str, effect_registry: &EffectRegistry) -> Result<Self, ConfigError> {
+        let file: PresetFile = config::deserialize(source)?;
+
+        let effects = match file.effects {
+            Some(effects) => config::effects(source, effects, effect_registry)?,
+            None => return Err(config::invalid(0, "effects", "missing, a preset needs at least one effect")),
+        };
+        let cursor = match file.cursor {
+            Some(cursor) => match cursor.get_ref().0.as_slice() {
+                &[x, y] => Some((x, y)),
+                values => {
+                    return Err(config::invalid_at(
+                        source,
+                        cursor.span().start,
+                        "cursor",
+                        format!("expected 2 values but got {}", values.len()),
+                    ))
+                }
+            },
+            None => None,
+        };
+        let mut parameters = Vec::new();
+        for (line, parameter) in config::parameters(source, file.parameters, effect_registry)? {
+            if !effects.contains(&parameter.effect) {
+                return Err(config::invalid(
+                    line,
+                    format!("parameters.{}.{}", parameter.effect, parameter.name),
+                    format!("{} is not one of the effects of the preset", parameter.effect),
+                ));
+            }
+            parameters.push(parameter);
+        }
+
+        Ok(Preset {
+            name: file.name,
+            effects,
+            parameters,
+            cursor,
+            time: file.time.map(|Number(time)| time),
+        })
+    }
+
+    /// reads a preset file, the name is the file name without extension if the file doesn't declare one
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load(path: &std::path::Path, effect_registry: &EffectRegistry) -> Result<Self, ConfigError> {
+        let source = std::fs::read_to_string(path).map_err(ConfigError::Io)?;
+        let mut preset = Self::parse(&source, effect_registry)?;
+        if preset.name.is_empty() {
+            preset.name = path.file_stem().unwrap_or_default().to_string_lossy().into_owned();
+        }
+        Ok(preset)
+    }
+
+    /// loads the `.toml` files of the directory ordered by file name, files that fail to load are logged and skipped
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load_dir(dir: &std::path::Path, effect_registry: &EffectRegistry) -> Vec<Self> {
+        let mut paths: Vec<_> = match std::fs::read_dir(dir) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.extension().is_some_and(|extension| extension == "toml"))
+                .collect(),
+            Err(_) => return Vec::new(),
+        };
+        paths.sort();
+        paths
+            .iter()
+            .filter_map(|path| match Self::load(path, effect_registry) {
+                Ok(preset) => Some(preset),
+                Err(error) => {
+                    log::error!("skipping the preset {}: {error}", path.display());
+                    None
+                }
+            })
+            .collect()
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn save(&self, path: &std::path::Path) -> std::io::Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, self.to_toml())
+    }
+
+    /// the preset in the format that `parse` reads
+    pub fn to_toml(&self) -> String {
+        let mut toml = format!("name = {}\n", quote(&self.name));
+        let effects: Vec<_> = self.effects.iter().map(|effect| quote(effect)).collect();
+        toml.push_str(&format!("effects = [{}]\n", effects.join(", ")));
+        if let Some((x, y)) = self.cursor {
+            toml.push_str(&format!("cursor = [{x}, {y}]\n"));
+        }
+        if let Some(time) = self.time {
+            toml.push_str(&format!("time = {time}\n"));
+        }
+
+        let mut table = None;
+        for parameter in &self.parameters {
+            if table != Some(&parameter.effect) {
+                toml.push_str(&format!("\n[parameters.{}]\n", key(&parameter.effect)));
+                table = Some(&parameter.effect);
+            }
+            let values = match parameter.values.as_slice() {
+                [value] => value.to_string(),
+                values => format!("[{}]", values.iter().map(f32::to_string).collect::<Vec<_>>().join(", ")),
+            };
+            toml.push_str(&format!("{} = {values}\n", key(&parameter.name)));
+        }
+        toml
+    }
+
+    /// selects the effects, resets their parameters to the defaults before the values of the preset are set
+    /// and moves the cursor and sets the time of the globals if the preset has them
+    pub fn apply(&self, device: &wgpu::Device, queue: &wgpu::Queue, post_processor: &mut PostProcessor) -> Result<(), ConfigError> {
+        config::set_chain(device, queue, post_processor, &self.effects)?;
+        for effect_index in post_processor.chain().to_vec() {
+            let effect = post_processor
+                .effect_mut(effect_index)
+                .expect("the effects of the chain are created");
+            if let Some(parameters) = effect.parameters_mut() {
+                for parameter in parameters.layout().parameters.clone() {
+                    parameters
+                        .set(&parameter.name, &parameter.default)
+                        .expect("the default has a value per component");
+                }
+            }
+        }
+        config::set_parameters(device, queue, post_processor, &self.parameters)?;
+
+        let globals = post_processor.globals_mut();
+        if let Some((x, y)) = self.cursor {
+            globals.cursor_x = x;
+            globals.cursor_y = y;
+        }
+        if let Some(time) = self.time {
+            globals.set_time(time);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PresetFile {
+    #[serde(default)]
+    name: String,
+    effects: Option<Spanned<Vec<Spanned<String>>>>,
+    cursor: Option<Spanned<Numbers>>,
+    time: Option<Number>,
+    #[serde(default)]
+    parameters: ParameterTables,
+}
+
+/// the name as a TOML key, quoted unless it is a bare key, e.g. names with spaces or dots
+fn key(name: &str) -> String {
+    match !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+        true => name.to_string(),
+        false => quote(name),
+    }
+}
+
+fn quote(string: &str) -> String {
+    format!(
+        "\"{}\"",
+        string
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n")
+            .replace('\t', "\\t")
+    )
+}
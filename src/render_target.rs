@@ -0,0 +1,123 @@
+use crate::post_processing;
+
+/// a texture that a pass renders to and the following post processing pass reads from
+///
+/// the bind group that the post processing passes use as input is created once together with the texture,
+/// so nothing has to be allocated per frame. Render targets only have to be recreated when the size or format changes.
+pub struct RenderTarget {
+    #[allow(unused)]
+    texture: wgpu::Texture,
+    view: wgpu::TextureView,
+    bind_group: wgpu::BindGroup,
+    /// only storage render targets can be written by compute shaders
+    storage_bind_group: Option<wgpu::BindGroup>,
+    size: wgpu::Extent3d,
+    format: wgpu::TextureFormat,
+}
+
+impl RenderTarget {
+    pub fn new(device: &wgpu::Device, size: wgpu::Extent3d, format: wgpu::TextureFormat, label: &str) -> Self {
+        Self::create(device, size, format, wgpu::TextureUsages::RENDER_ATTACHMENT, label)
+    }
+
+    /// a render target that compute shaders write to, see `post_processing::STORAGE_BIND_GROUP_LAYOUT`
+    pub fn storage(device: &wgpu::Device, size: wgpu::Extent3d, label: &str) -> Self {
+        Self::create(
+            device,
+            size,
+            post_processing::STORAGE_TEXTURE_FORMAT,
+            wgpu::TextureUsages::STORAGE_BINDING,
+            label,
+        )
+    }
+
+    fn create(device: &wgpu::Device, size: wgpu::Extent3d, format: wgpu::TextureFormat, usage: wgpu::TextureUsages, label: &str) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(label),
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            mip_level_count: 1,
+            sample_count: 1,
+            size,
+            usage: usage | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let bind_group = create_input_bind_group(device, &view);
+
+        let storage_bind_group = usage.contains(wgpu::TextureUsages::STORAGE_BINDING).then(|| {
+            let storage_bind_group_layout = device.create_bind_group_layout(post_processing::STORAGE_BIND_GROUP_LAYOUT);
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                layout: &storage_bind_group_layout,
+                entries: &[wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                }],
+                label: Some("storage_bind_group"),
+            })
+        });
+
+        Self {
+            texture,
+            view,
+            bind_group,
+            storage_bind_group,
+            size,
+            format,
+        }
+    }
+
+    pub fn view(&self) -> &wgpu::TextureView {
+        &self.view
+    }
+
+    /// the bind group of the texture and its sampler as expected by the post processing shaders at group 1,
+    /// see `create_input_bind_group`
+    pub fn bind_group(&self) -> &wgpu::BindGroup {
+        &self.bind_group
+    }
+
+    pub fn size(&self) -> wgpu::Extent3d {
+        self.size
+    }
+
+    /// the bind group of the storage texture as expected by the compute shaders at group 3
+    pub fn storage_bind_group(&self) -> Option<&wgpu::BindGroup> {
+        self.storage_bind_group.as_ref()
+    }
+
+    /// whether the render target has to be recreated for the given size and format
+    pub fn matches(&self, size: wgpu::Extent3d, format: wgpu::TextureFormat) -> bool {
+        self.size == size && self.format == format
+    }
+}
+
+/// the bind group of a texture view and a sampler as expected by the post processing shaders at group 1,
+/// see `post_processing::TEXTURE_BIND_GROUP_LAYOUT`. The view can belong to a texture of the host application.
+pub fn create_input_bind_group(device: &wgpu::Device, view: &wgpu::TextureView) -> wgpu::BindGroup {
+    let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+        address_mode_u: wgpu::AddressMode::ClampToEdge,
+        address_mode_v: wgpu::AddressMode::ClampToEdge,
+        address_mode_w: wgpu::AddressMode::ClampToEdge,
+        mag_filter: wgpu::FilterMode::Linear,
+        min_filter: wgpu::FilterMode::Nearest,
+        mipmap_filter: wgpu::FilterMode::Nearest,
+        ..Default::default()
+    });
+
+    let bind_group_layout = device.create_bind_group_layout(post_processing::TEXTURE_BIND_GROUP_LAYOUT);
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        layout: &bind_group_layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::Sampler(&sampler),
+            },
+        ],
+        label: Some("diffuse_bind_group"),
+    })
+}
@@ -1,144 +1,393 @@
-pub struct Scene {
+//! the sources of the image that the post processing chain is applied to
+//!
+//! every scene renders into the render target that the first effect of the chain reads from:
+//! `TextureScene` draws an image or a texture of the host application, `ProceduralScene` runs a WGSL fragment shader
+//! and `FrameBufferScene` uploads pixels that are written on the CPU.
+
+use crate::shader_globals::Globals;
+use crate::shader_preprocessor::{self, PreprocessError};
+use crate::texture::Texture;
+
+pub trait Scene {
+    /// called once per frame before `encode`, e.g. to upload changed pixels
+    fn update(&mut self, _queue: &wgpu::Queue, _globals: &Globals) {}
+
+    /// renders the scene into the dst_view
+    fn encode(&self, encoder: &mut wgpu::CommandEncoder, dst_view: &wgpu::TextureView, globals_bind_group: &wgpu::BindGroup);
+}
+
+/// stretches a texture over the whole render target
+pub struct TextureScene {
     render_pipeline: wgpu::RenderPipeline,
+    texture_bind_group_layout: wgpu::BindGroupLayout,
+    view: wgpu::TextureView,
+    sampler: wgpu::Sampler,
     diffuse_bind_group: wgpu::BindGroup,
 }
 
-impl Scene {
+impl TextureScene {
+    /// creates the scene with the embedded xsware brand image
     pub fn new(
         device: &wgpu::Device,
         queue: &wgpu::Queue,
         format: wgpu::TextureFormat,
         globals_bind_group_layout: &wgpu::BindGroupLayout,
     ) -> Self {
-        let shader = device.create_shader_module(wgpu::include_wgsl!("shaders/scene.wgsl"));
-
         let diffuse_bytes = include_bytes!("xsware_brand.png");
-        let diffuse_texture = crate::texture::Texture::from_bytes(device, queue, diffuse_bytes, "xsware_brand.png");
-
-        let texture_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            entries: &[
-                wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Texture {
-                        multisampled: false,
-                        view_dimension: wgpu::TextureViewDimension::D2,
-                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
-                    },
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 1,
-                    visibility: wgpu::ShaderStages::FRAGMENT,
-                    // This should match the filterable field of the
-                    // corresponding Texture entry above.
-                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
-                    count: None,
-                },
-            ],
-            label: Some("texture_bind_group_layout"),
-        });
+        let diffuse_texture = Texture::from_bytes(device, queue, diffuse_bytes, "xsware_brand.png");
+        Self::create(device, format, globals_bind_group_layout, diffuse_texture.view)
+    }
 
-        let diffuse_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            layout: &texture_bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: wgpu::BindingResource::TextureView(&diffuse_texture.view),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: wgpu::BindingResource::Sampler(&diffuse_texture.sampler),
-                },
-            ],
-            label: Some("diffuse_bind_group"),
-        });
+    /// creates a scene that shows the given image
+    pub fn from_image(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        format: wgpu::TextureFormat,
+        globals_bind_group_layout: &wgpu::BindGroupLayout,
+        img: &image::DynamicImage,
+        label: Option<&str>,
+    ) -> Self {
+        let diffuse_texture = Texture::from_image(device, queue, img, label);
+        Self::create(device, format, globals_bind_group_layout, diffuse_texture.view)
+    }
 
-        let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-            label: Some("scene render pipeline layout"),
-            bind_group_layouts: &[globals_bind_group_layout, &texture_bind_group_layout],
-            push_constant_ranges: &[],
-        });
+    /// creates a scene that shows the image file at the path, in any format that the `image` crate can read
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn from_path(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        format: wgpu::TextureFormat,
+        globals_bind_group_layout: &wgpu::BindGroupLayout,
+        path: &std::path::Path,
+    ) -> Result<Self, image::ImageError> {
+        let img = image::open(path)?;
+        let label = path.to_string_lossy();
+        Ok(Self::from_image(
+            device,
+            queue,
+            format,
+            globals_bind_group_layout,
+            &img,
+            Some(&label),
+        ))
+    }
 
-        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("scene render pipeline"),
-            layout: Some(&render_pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &shader,
-                entry_point: "vertex",
-                buffers: &[],
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &shader,
-                entry_point: "fragment",
-                targets: &[Some(wgpu::ColorTargetState {
-                    format,
-                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-            }),
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList,
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Ccw,
-                cull_mode: Some(wgpu::Face::Back),
-                // Setting this to anything other than Fill requires Features::NON_FILL_POLYGON_MODE
-                polygon_mode: wgpu::PolygonMode::Fill,
-                // Requires Features::DEPTH_CLIP_CONTROL
-                unclipped_depth: false,
-                // Requires Features::CONSERVATIVE_RASTERIZATION
-                conservative: false,
-            },
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState {
-                count: 1,
-                mask: !0,
-                alpha_to_coverage_enabled: false,
-            },
-            multiview: None,
-            cache: None,
-        });
+    /// creates a scene that shows a texture of the host application, e.g. the output of its own renderer.
+    /// The texture needs `TEXTURE_BINDING` usage and a filterable float format.
+    pub fn from_view(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        globals_bind_group_layout: &wgpu::BindGroupLayout,
+        view: wgpu::TextureView,
+    ) -> Self {
+        Self::create(device, format, globals_bind_group_layout, view)
+    }
+
+    /// replaces the shown texture, e.g. after the host application recreated it with a new size
+    pub fn set_view(&mut self, device: &wgpu::Device, view: wgpu::TextureView) {
+        self.diffuse_bind_group = create_texture_bind_group(device, &self.texture_bind_group_layout, &view, &self.sampler);
+        self.view = view;
+    }
+
+    /// filters the texture with the given mode when it is scaled to the render target,
+    /// by default it is magnified with linear and minified with nearest filtering
+    pub fn set_filter_mode(&mut self, device: &wgpu::Device, filter_mode: wgpu::FilterMode) {
+        self.sampler = create_sampler(device, filter_mode, filter_mode);
+        self.diffuse_bind_group = create_texture_bind_group(device, &self.texture_bind_group_layout, &self.view, &self.sampler);
+    }
+
+    fn create(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        globals_bind_group_layout: &wgpu::BindGroupLayout,
+        view: wgpu::TextureView,
+    ) -> Self {
+        let shader = shader_preprocessor::create_embedded_shader_module(device, "scene.wgsl");
+        let texture_bind_group_layout = create_texture_bind_group_layout(device);
+        let render_pipeline = create_render_pipeline(device, format, &[globals_bind_group_layout, &texture_bind_group_layout], &shader);
+
+        // the same filtering as the textures of `Texture::from_image`
+        let sampler = create_sampler(device, wgpu::FilterMode::Linear, wgpu::FilterMode::Nearest);
+        let diffuse_bind_group = create_texture_bind_group(device, &texture_bind_group_layout, &view, &sampler);
 
         Self {
             render_pipeline,
+            texture_bind_group_layout,
+            view,
+            sampler,
             diffuse_bind_group,
         }
     }
+}
+
+impl Scene for TextureScene {
+    fn encode(&self, encoder: &mut wgpu::CommandEncoder, dst_view: &wgpu::TextureView, globals_bind_group: &wgpu::BindGroup) {
+        draw(
+            encoder,
+            &self.render_pipeline,
+            dst_view,
+            &[globals_bind_group, &self.diffuse_bind_group],
+        );
+    }
+}
+
+/// a scene that is drawn by the `fragment` function of a WGSL shader
+///
+/// the shader includes `prelude.wgsl` like the effects, but only the globals are bound and not the input texture.
+pub struct ProceduralScene {
+    render_pipeline: wgpu::RenderPipeline,
+}
+
+impl ProceduralScene {
+    /// `file` is the label of the shader in error messages. The shader can include the embedded shaders, e.g. `color.wgsl`.
+    /// WGSL errors are reported by wgpu like those of any other shader module.
+    pub fn new(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        globals_bind_group_layout: &wgpu::BindGroupLayout,
+        file: &str,
+        source: &str,
+    ) -> Result<Self, PreprocessError> {
+        let load = |included_file: &str| match included_file == file {
+            true => Some(source.to_string()),
+            false => shader_preprocessor::embedded_source(included_file).map(str::to_string),
+        };
+        let preprocessed = shader_preprocessor::preprocess(file, &load)?;
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some(file),
+            source: wgpu::ShaderSource::Wgsl(preprocessed.source.into()),
+        });
+        let render_pipeline = create_render_pipeline(device, format, &[globals_bind_group_layout], &shader);
+
+        Ok(Self { render_pipeline })
+    }
+}
+
+impl Scene for ProceduralScene {
+    fn encode(&self, encoder: &mut wgpu::CommandEncoder, dst_view: &wgpu::TextureView, globals_bind_group: &wgpu::BindGroup) {
+        draw(encoder, &self.render_pipeline, dst_view, &[globals_bind_group]);
+    }
+}
+
+/// a scene of sRGB pixels that are written on the CPU, e.g. by a software renderer or an emulator
+///
+/// changes to `frame_mut` are uploaded once in the following `update`.
+pub struct FrameBufferScene {
+    scene: TextureScene,
+    texture: Texture,
+    frame: image::RgbaImage,
+    changed: bool,
+}
+
+impl FrameBufferScene {
+    /// creates a black frame of the given size
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        format: wgpu::TextureFormat,
+        globals_bind_group_layout: &wgpu::BindGroupLayout,
+        width: u32,
+        height: u32,
+    ) -> Self {
+        let frame = image::RgbaImage::from_pixel(width, height, image::Rgba([0, 0, 0, 255]));
+        let texture = Texture::from_image(device, queue, &frame.clone().into(), Some("frame buffer"));
+        let scene = TextureScene::from_view(
+            device,
+            format,
+            globals_bind_group_layout,
+            texture.texture.create_view(&wgpu::TextureViewDescriptor::default()),
+        );
+        Self {
+            scene,
+            texture,
+            frame,
+            changed: false,
+        }
+    }
+
+    pub fn frame(&self) -> &image::RgbaImage {
+        &self.frame
+    }
 
-    /// renders the scene
-    pub fn render_pass(
-        &self,
-        encoder: &mut wgpu::CommandEncoder,
-        dst_view: &wgpu::TextureView,
-        globals_bind_group: &wgpu::BindGroup,
-    ) -> Result<(), wgpu::SurfaceError> {
-        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-            label: Some("scene render pass"),
-            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                view: dst_view,
-                resolve_target: None,
-                ops: wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(wgpu::Color {
-                        r: 0.01,
-                        g: 0.01,
-                        b: 0.01,
-                        a: 1.0,
-                    }),
-                    store: wgpu::StoreOp::Store,
+    /// the pixels of the frame, the size can't be changed
+    pub fn frame_mut(&mut self) -> &mut image::RgbaImage {
+        self.changed = true;
+        &mut self.frame
+    }
+}
+
+impl Scene for FrameBufferScene {
+    fn update(&mut self, queue: &wgpu::Queue, _globals: &Globals) {
+        if !self.changed {
+            return;
+        }
+        self.changed = false;
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                aspect: wgpu::TextureAspect::All,
+                texture: &self.texture.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+            },
+            &self.frame,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(4 * self.frame.width()),
+                rows_per_image: Some(self.frame.height()),
+            },
+            self.texture.texture.size(),
+        );
+    }
+
+    fn encode(&self, encoder: &mut wgpu::CommandEncoder, dst_view: &wgpu::TextureView, globals_bind_group: &wgpu::BindGroup) {
+        self.scene.encode(encoder, dst_view, globals_bind_group);
+    }
+}
+
+fn create_texture_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    multisampled: false,
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
                 },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                // This should match the filterable field of the
+                // corresponding Texture entry above.
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                count: None,
+            },
+        ],
+        label: Some("texture_bind_group_layout"),
+    })
+}
+
+fn create_sampler(device: &wgpu::Device, mag_filter: wgpu::FilterMode, min_filter: wgpu::FilterMode) -> wgpu::Sampler {
+    device.create_sampler(&wgpu::SamplerDescriptor {
+        address_mode_u: wgpu::AddressMode::ClampToEdge,
+        address_mode_v: wgpu::AddressMode::ClampToEdge,
+        address_mode_w: wgpu::AddressMode::ClampToEdge,
+        mag_filter,
+        min_filter,
+        mipmap_filter: wgpu::FilterMode::Nearest,
+        ..Default::default()
+    })
+}
+
+fn create_texture_bind_group(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    view: &wgpu::TextureView,
+    sampler: &wgpu::Sampler,
+) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::Sampler(sampler),
+            },
+        ],
+        label: Some("diffuse_bind_group"),
+    })
+}
+
+fn create_render_pipeline(
+    device: &wgpu::Device,
+    format: wgpu::TextureFormat,
+    bind_group_layouts: &[&wgpu::BindGroupLayout],
+    shader: &wgpu::ShaderModule,
+) -> wgpu::RenderPipeline {
+    let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("scene render pipeline layout"),
+        bind_group_layouts,
+        push_constant_ranges: &[],
+    });
+
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("scene render pipeline"),
+        layout: Some(&render_pipeline_layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: "vertex",
+            buffers: &[],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: "fragment",
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                write_mask: wgpu::ColorWrites::ALL,
             })],
-            depth_stencil_attachment: None,
-            occlusion_query_set: None,
-            timestamp_writes: None,
-        });
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: Some(wgpu::Face::Back),
+            // Setting this to anything other than Fill requires Features::NON_FILL_POLYGON_MODE
+            polygon_mode: wgpu::PolygonMode::Fill,
+            // Requires Features::DEPTH_CLIP_CONTROL
+            unclipped_depth: false,
+            // Requires Features::CONSERVATIVE_RASTERIZATION
+            conservative: false,
+        },
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState {
+            count: 1,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+        multiview: None,
+        cache: None,
+    })
+}
 
-        render_pass.set_pipeline(&self.render_pipeline);
-        render_pass.set_bind_group(0, globals_bind_group, &[]);
-        render_pass.set_bind_group(1, &self.diffuse_bind_group, &[]);
-        render_pass.draw(0..6, 0..1);
+/// clears the dst_view and draws the fullscreen rectangle of `prelude.wgsl` with the bind groups starting at group 0
+fn draw(
+    encoder: &mut wgpu::CommandEncoder,
+    render_pipeline: &wgpu::RenderPipeline,
+    dst_view: &wgpu::TextureView,
+    bind_groups: &[&wgpu::BindGroup],
+) {
+    let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some("scene render pass"),
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+            view: dst_view,
+            resolve_target: None,
+            ops: wgpu::Operations {
+                load: wgpu::LoadOp::Clear(wgpu::Color {
+                    r: 0.01,
+                    g: 0.01,
+                    b: 0.01,
+                    a: 1.0,
+                }),
+                store: wgpu::StoreOp::Store,
+            },
+        })],
+        depth_stencil_attachment: None,
+        occlusion_query_set: None,
+        timestamp_writes: None,
+    });
 
-        Ok(())
+    render_pass.set_pipeline(render_pipeline);
+    for (index, bind_group) in bind_groups.iter().enumerate() {
+        render_pass.set_bind_group(index as u32, bind_group, &[]);
     }
+    render_pass.draw(0..6, 0..1);
 }
@@ -1,11 +1,33 @@
+/// bits of `Globals::buttons`, the same constants are declared in `prelude.wgsl`
+pub const BUTTON_LEFT: u32 = 1 << 0;
+pub const BUTTON_RIGHT: u32 = 1 << 1;
+pub const BUTTON_MIDDLE: u32 = 1 << 2;
+pub const BUTTON_TOUCH: u32 = 1 << 3;
+
+/// has to match the `Globals` struct in `prelude.wgsl`
 #[repr(C, align(16))]
 #[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct Globals {
+    /// seconds since the start
     pub time: f32,
+    /// cursor position from -1 to 1, (-1, -1) is the top left corner
     pub cursor_x: f32,
     pub cursor_y: f32,
+    /// seconds since the previous frame
+    pub delta_time: f32,
+    /// size of the surface in pixels
+    pub resolution_x: f32,
+    pub resolution_y: f32,
+    /// width divided by height of the surface
+    pub aspect: f32,
+    /// number of the frame, starts at 0
+    pub frame: u32,
+    /// pressed mouse buttons and touch state, see `BUTTON_LEFT` etc.
+    pub buttons: u32,
+    /// random number that changes every frame
+    pub seed: u32,
     // WebGL needs 16 byte alignment
-    padding: f32,
+    padding: [u32; 2],
 }
 
 pub const BIND_GROUP_LAYOUT: &wgpu::BindGroupLayoutDescriptor = &wgpu::BindGroupLayoutDescriptor {
@@ -18,7 +40,7 @@ pub const BIND_GROUP_LAYOUT: &wgpu::BindGroupLayoutDescriptor = &wgpu::BindGroup
             has_dynamic_offset: false,
             min_binding_size: None,
         },
-        visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+        visibility: wgpu::ShaderStages::VERTEX_FRAGMENT.union(wgpu::ShaderStages::COMPUTE),
     }],
 };
 
@@ -28,7 +50,52 @@ impl Globals {
             time: 0.0,
             cursor_x: 0.0,
             cursor_y: 0.0,
-            padding: 0.0,
+            delta_time: 0.0,
+            resolution_x: 1.0,
+            resolution_y: 1.0,
+            aspect: 1.0,
+            frame: 0,
+            buttons: 0,
+            seed: 0,
+            padding: [0; 2],
         }
     }
+
+    pub fn set_resolution(&mut self, width: u32, height: u32) {
+        self.resolution_x = width as f32;
+        self.resolution_y = height as f32;
+        self.aspect = width as f32 / height.max(1) as f32;
+    }
+
+    /// sets the time of the current frame and the time since the previous frame
+    pub fn set_time(&mut self, time: f32) {
+        self.delta_time = (time - self.time).max(0.0);
+        self.time = time;
+    }
+
+    /// increments the frame number and derives a new seed, call it after a frame was rendered
+    pub fn next_frame(&mut self) {
+        self.frame = self.frame.wrapping_add(1);
+        self.seed = hash(self.seed ^ self.frame);
+    }
+
+    pub fn set_button(&mut self, button: u32, pressed: bool) {
+        match pressed {
+            true => self.buttons |= button,
+            false => self.buttons &= !button,
+        }
+    }
+}
+
+/// PCG hash, good enough to derive a new seed every frame without a random number crate
+fn hash(value: u32) -> u32 {
+    let state = value.wrapping_mul(747796405).wrapping_add(2891336453);
+    let word = ((state >> ((state >> 28) + 4)) ^ state).wrapping_mul(277803737);
+    (word >> 22) ^ word
+}
+
+impl Default for Globals {
+    fn default() -> Self {
+        Self::new()
+    }
 }
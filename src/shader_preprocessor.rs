@@ -0,0 +1,208 @@
+//! resolves `#include "file.wgsl"` directives in WGSL shaders before they are passed to `create_shader_module`.
+//! Every file is included at most once, so shared files like `prelude.wgsl` can be included from anywhere.
+
+use crate::lut::LutLayout;
+
+/// shader files that are embedded into the binary and can be included by any shader, paths are relative to `src/shaders`.
+/// The effect shaders are embedded by the `EffectRegistry`.
+const EMBEDDED_SHADERS: &[(&str, &str)] = &[
+    ("color.wgsl", include_str!("shaders/color.wgsl")),
+    ("compute.wgsl", include_str!("shaders/compute.wgsl")),
+    ("copy.wgsl", include_str!("shaders/copy.wgsl")),
+    ("edge.wgsl", include_str!("shaders/edge.wgsl")),
+    ("lut.wgsl", LUT_SHADER),
+    ("multipass.wgsl", include_str!("shaders/multipass.wgsl")),
+    ("prelude.wgsl", include_str!("shaders/prelude.wgsl")),
+    ("scene.wgsl", include_str!("shaders/scene.wgsl")),
+    ("shadertoy.wgsl", include_str!("shaders/shadertoy.wgsl")),
+];
+
+/// the declarations of LUT effects for the `LutLayout` of this build
+const LUT_SHADER: &str = match LutLayout::CURRENT {
+    LutLayout::Volume => include_str!("shaders/lut.wgsl"),
+    LutLayout::Strip => include_str!("shaders/lut_strip.wgsl"),
+};
+
+const INCLUDE_DIRECTIVE: &str = "#include";
+
+#[derive(Debug)]
+pub enum PreprocessError {
+    /// `load` did not return a source for the file
+    NotFound {
+        file: String,
+        included_from: Option<String>,
+    },
+    InvalidDirective {
+        file: String,
+        line: usize,
+        directive: String,
+    },
+}
+
+impl std::fmt::Display for PreprocessError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PreprocessError::NotFound {
+                file,
+                included_from: Some(included_from),
+            } => write!(f, "could not load {file} included from {included_from}"),
+            PreprocessError::NotFound { file, included_from: None } => write!(f, "could not load {file}"),
+            PreprocessError::InvalidDirective { file, line, directive } => {
+                write!(f, "{file}:{line}: invalid directive '{directive}', expected #include \"file.wgsl\"")
+            }
+        }
+    }
+}
+
+impl std::error::Error for PreprocessError {}
+
+pub struct PreprocessedShader {
+    pub source: String,
+    /// the processed file followed by every file it included
+    pub files: Vec<String>,
+}
+
+/// loads the file and recursively replaces its include directives with the content of the included files
+pub fn preprocess(file: &str, load: &dyn Fn(&str) -> Option<String>) -> Result<PreprocessedShader, PreprocessError> {
+    let mut shader = PreprocessedShader {
+        source: String::new(),
+        files: Vec::new(),
+    };
+    append_file(&mut shader, file, None, load)?;
+    Ok(shader)
+}
+
+fn append_file(
+    shader: &mut PreprocessedShader,
+    file: &str,
+    included_from: Option<&str>,
+    load: &dyn Fn(&str) -> Option<String>,
+) -> Result<(), PreprocessError> {
+    if shader.files.iter().any(|included| included == file) {
+        return Ok(());
+    }
+    shader.files.push(file.to_string());
+
+    let source = load(file).ok_or_else(|| PreprocessError::NotFound {
+        file: file.to_string(),
+        included_from: included_from.map(str::to_string),
+    })?;
+
+    for (line_index, line) in source.lines().enumerate() {
+        let trimmed = line.trim();
+        if !trimmed.starts_with('#') {
+            shader.source.push_str(line);
+            shader.source.push('\n');
+            continue;
+        }
+
+        let included_file = trimmed
+            .strip_prefix(INCLUDE_DIRECTIVE)
+            .map(str::trim)
+            .and_then(|argument| argument.strip_prefix('"')?.strip_suffix('"'))
+            .ok_or_else(|| PreprocessError::InvalidDirective {
+                file: file.to_string(),
+                line: line_index + 1,
+                directive: trimmed.to_string(),
+            })?;
+        append_file(shader, included_file, Some(file), load)?;
+    }
+
+    Ok(())
+}
+
+/// returns the embedded source of a shader file
+pub fn embedded_source(file: &str) -> Option<&'static str> {
+    EMBEDDED_SHADERS
+        .iter()
+        .find(|(embedded_file, _)| *embedded_file == file)
+        .map(|(_, source)| *source)
+}
+
+/// preprocesses an embedded shader file and creates the shader module from it
+pub fn create_embedded_shader_module(device: &wgpu::Device, file: &str) -> wgpu::ShaderModule {
+    let source = embedded_source(file).unwrap_or_else(|| panic!("{} is not embedded", file));
+    create_shader_module(device, file, source)
+}
+
+/// preprocesses the source of the given file with the embedded shaders as includes and creates the shader module from it
+pub fn create_shader_module(device: &wgpu::Device, file: &str, source: &str) -> wgpu::ShaderModule {
+    let load = |included_file: &str| match included_file == file {
+        true => Some(source.to_string()),
+        false => embedded_source(included_file).map(str::to_string),
+    };
+    // the embedded shaders are part of the binary, so a missing include is a bug and not a runtime error
+    let shader = preprocess(file, &load).unwrap_or_else(|error| panic!("{}", error));
+    device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some(file),
+        source: wgpu::ShaderSource::Wgsl(shader.source.into()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn files<'a>(files: &'a [(&'a str, &'a str)]) -> impl Fn(&str) -> Option<String> + 'a {
+        move |file| files.iter().find(|(name, _)| *name == file).map(|(_, source)| source.to_string())
+    }
+
+    #[test]
+    fn nested_and_repeated_includes_are_included_once() {
+        let load = files(&[
+            ("main.wgsl", "#include \"a.wgsl\"\n#include \"b.wgsl\"\n#include \"a.wgsl\"\nmain"),
+            ("a.wgsl", "#include \"common.wgsl\"\na"),
+            ("b.wgsl", "  #include \"common.wgsl\"\nb"),
+            ("common.wgsl", "common"),
+        ]);
+        let shader = preprocess("main.wgsl", &load).expect("all files exist");
+        assert_eq!(shader.source, "common\na\nb\nmain\n");
+        assert_eq!(shader.files, ["main.wgsl", "a.wgsl", "common.wgsl", "b.wgsl"]);
+    }
+
+    #[test]
+    fn a_file_that_includes_itself_is_included_once() {
+        let load = files(&[("main.wgsl", "#include \"main.wgsl\"\nmain")]);
+        assert_eq!(preprocess("main.wgsl", &load).expect("the file exists").source, "main\n");
+    }
+
+    #[test]
+    fn missing_files_name_the_including_file() {
+        let load = files(&[("main.wgsl", "#include \"missing.wgsl\"")]);
+        match preprocess("main.wgsl", &load) {
+            Err(PreprocessError::NotFound { file, included_from }) => {
+                assert_eq!(file, "missing.wgsl");
+                assert_eq!(included_from.as_deref(), Some("main.wgsl"));
+            }
+            result => panic!("expected a missing file but got {:?}", result.map(|shader| shader.source)),
+        }
+        assert!(matches!(
+            preprocess("missing.wgsl", &load),
+            Err(PreprocessError::NotFound { included_from: None, .. })
+        ));
+    }
+
+    #[test]
+    fn malformed_directives_are_rejected_with_their_line() {
+        for directive in [
+            "#include prelude.wgsl",
+            "#include \"prelude.wgsl",
+            "#import \"prelude.wgsl\"",
+            "#include",
+        ] {
+            let source = format!("fn a() {{}}\n{directive}");
+            let sources = [("main.wgsl", source.as_str()), ("prelude.wgsl", "")];
+            let load = files(&sources);
+            match preprocess("main.wgsl", &load) {
+                Err(PreprocessError::InvalidDirective {
+                    file,
+                    line,
+                    directive: actual,
+                }) => {
+                    assert_eq!((file.as_str(), line, actual.as_str()), ("main.wgsl", 2, directive));
+                }
+                result => panic!("expected an invalid directive but got {:?}", result.map(|shader| shader.source)),
+            }
+        }
+    }
+}
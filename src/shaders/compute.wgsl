@@ -0,0 +1,7 @@
+// declarations of compute effects, the `EffectRegistry` includes it into effects of the `Compute` kind.
+// A compute effect declares `@compute @workgroup_size(8, 8) fn compute(@builtin(global_invocation_id) id: vec3<u32>)`,
+// reads the input with `textureLoad(t_diffuse, ...)` and writes every pixel of the output with `textureStore(output, ...)`.
+#include "prelude.wgsl"
+
+@group(3) @binding(0)
+var output: texture_storage_2d<rgba16float, write>;
@@ -0,0 +1,51 @@
+// @param strength f32 min=0 max=1 default=1
+
+// box blur that loads the pixels of a workgroup and its border into workgroup memory once,
+// instead of reading every pixel from the texture 81 times
+const RADIUS: i32 = 4;
+// 8 + 2 * RADIUS pixels
+const TILE_SIZE: i32 = 16;
+
+var<workgroup> tile: array<array<vec4<f32>, 16>, 16>;
+
+@compute @workgroup_size(8, 8)
+fn compute(
+    @builtin(global_invocation_id) global_id: vec3<u32>,
+    @builtin(local_invocation_id) local_id: vec3<u32>,
+    @builtin(workgroup_id) workgroup_id: vec3<u32>,
+) {
+    let size = vec2<i32>(textureDimensions(t_diffuse));
+    let tile_origin = vec2<i32>(workgroup_id.xy) * 8 - RADIUS;
+
+    // every invocation loads 4 of the 16x16 pixels
+    for (var i = 0; i < 4; i++) {
+        let index = i32(local_id.y * 8u + local_id.x) + i * 64;
+        let tile_position = vec2<i32>(index % TILE_SIZE, index / TILE_SIZE);
+        let position = clamp(tile_origin + tile_position, vec2<i32>(0), size - 1);
+        tile[tile_position.y][tile_position.x] = textureLoad(t_diffuse, position, 0);
+    }
+    workgroupBarrier();
+
+    let position = vec2<i32>(global_id.xy);
+    if position.x >= size.x || position.y >= size.y {
+        return;
+    }
+
+    let local = vec2<i32>(local_id.xy);
+    let original = tile[local.y + RADIUS][local.x + RADIUS];
+    // uv of the pixel center, the same split as the fragment shader effects
+    let uv_x = (f32(position.x) + 0.5) / f32(size.x);
+    if uv_x > (globals.cursor_x + 1) / 2 {
+        textureStore(output, position, original);
+        return;
+    }
+
+    var sum = vec4<f32>(0.0);
+    for (var y = 0; y <= 2 * RADIUS; y++) {
+        for (var x = 0; x <= 2 * RADIUS; x++) {
+            sum += tile[local.y + y][local.x + x];
+        }
+    }
+    let blurred = sum / f32((2 * RADIUS + 1) * (2 * RADIUS + 1));
+    textureStore(output, position, mix(original, blurred, parameters.strength));
+}
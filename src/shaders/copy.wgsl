@@ -0,0 +1,7 @@
+// copies the input texture, shows the output of compute effects and replaces them where compute shaders are not available
+#include "prelude.wgsl"
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(t_diffuse, s_diffuse, in.uv);
+}
@@ -0,0 +1,48 @@
+// 3x3 edge detection kernels on the brightness of the input, included by the edge and toon effects after the prelude.
+// The kernels are normalized, so a hard step from black to white has a strength of 1 for all of them.
+#include "color.wgsl"
+
+// perceived brightness of the sRGB encoded color, `textureSampleLevel` because the effects sample after branches
+fn edge_luma(uv: vec2<f32>) -> f32 {
+    let color = textureSampleLevel(t_diffuse, s_diffuse, uv, 0.0).rgb;
+    return dot(linear_to_srgb(color), vec3<f32>(0.299, 0.587, 0.114));
+}
+
+// brightness of the 3x3 neighborhood row by row from the top left, the neighbors are `spacing` texels apart
+fn neighborhood(uv: vec2<f32>, spacing: f32) -> array<f32, 9> {
+    let offset = texel_size() * spacing;
+    var luma: array<f32, 9>;
+    for (var y = -1; y <= 1; y++) {
+        for (var x = -1; x <= 1; x++) {
+            luma[(y + 1) * 3 + x + 1] = edge_luma(uv + vec2<f32>(f32(x), f32(y)) * offset);
+        }
+    }
+    return luma;
+}
+
+// gradient magnitude of the kernel with the given weight of the center row and column, 2 for Sobel and 1 for Prewitt
+fn gradient(l: array<f32, 9>, center_weight: f32) -> f32 {
+    let horizontal = (l[2] + center_weight * l[5] + l[8]) - (l[0] + center_weight * l[3] + l[6]);
+    let vertical = (l[6] + center_weight * l[7] + l[8]) - (l[0] + center_weight * l[1] + l[2]);
+    return length(vec2<f32>(horizontal, vertical)) / (center_weight + 2.0);
+}
+
+fn sobel(uv: vec2<f32>, spacing: f32) -> f32 {
+    return gradient(neighborhood(uv, spacing), 2.0);
+}
+
+fn prewitt(uv: vec2<f32>, spacing: f32) -> f32 {
+    return gradient(neighborhood(uv, spacing), 1.0);
+}
+
+// second derivative with the 8 neighbors kernel, responds to lines and corners in every direction
+fn laplacian(uv: vec2<f32>, spacing: f32) -> f32 {
+    let l = neighborhood(uv, spacing);
+    let neighbors = l[0] + l[1] + l[2] + l[3] + l[5] + l[6] + l[7] + l[8];
+    return abs(neighbors - 8.0 * l[4]) / 3.0;
+}
+
+// 0 below the threshold and 1 for clear edges, with a short transition against flickering lines
+fn edge_mask(strength: f32, threshold: f32) -> f32 {
+    return smoothstep(threshold, threshold + 0.1, strength);
+}
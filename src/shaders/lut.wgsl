@@ -0,0 +1,30 @@
+// declarations of LUT effects, the `EffectRegistry` includes it into effects of the `Lut` kind.
+// The lookup table of the effect is a 3D texture, the WebGL build includes `lut_strip.wgsl` instead.
+#include "prelude.wgsl"
+#include "color.wgsl"
+
+// has to match `LutInfo` in lut.rs
+struct LutInfo {
+    // the input values that map to the first and last points of the table
+    domain_min: vec3<f32>,
+    // number of points along each axis
+    size: f32,
+    domain_max: vec3<f32>,
+    // WebGL needs 16 byte alignment
+    padding: f32,
+}
+@group(3) @binding(0)
+var t_lut: texture_3d<f32>;
+@group(3) @binding(1)
+var s_lut: sampler;
+@group(3) @binding(2)
+var<uniform> lut_info: LutInfo;
+
+// looks up the linear color in the table. Tables are made for sRGB encoded colors like in image editors,
+// so the color is encoded before and decoded after the lookup.
+fn lut_color(color: vec3<f32>) -> vec3<f32> {
+    let position = clamp((linear_to_srgb(color) - lut_info.domain_min) / (lut_info.domain_max - lut_info.domain_min), vec3<f32>(0.0), vec3<f32>(1.0));
+    // the first and last points are at the centers of the first and last pixels
+    let uvw = (position * (lut_info.size - 1.0) + 0.5) / lut_info.size;
+    return srgb_to_linear(textureSampleLevel(t_lut, s_lut, uvw, 0.0).rgb);
+}
@@ -0,0 +1,12 @@
+// @param strength f32 min=0 max=1 default=1
+
+// grades the colors with the lookup table of the effect, a .cube file that can be replaced at runtime
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    let pixel = textureSample(t_diffuse, s_diffuse, in.uv);
+    if in.uv.x > (globals.cursor_x + 1) / 2 {
+        return pixel;
+    }
+    let graded = lut_color(pixel.rgb);
+    return vec4<f32>(mix(pixel.rgb, graded, parameters.strength), pixel.a);
+}
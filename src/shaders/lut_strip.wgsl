@@ -0,0 +1,32 @@
+// the declarations of `lut.wgsl` for the WebGL build, where the lookup table is a 2D texture with the blue slices next to each other
+#include "prelude.wgsl"
+#include "color.wgsl"
+
+// has to match `LutInfo` in lut.rs
+struct LutInfo {
+    // the input values that map to the first and last points of the table
+    domain_min: vec3<f32>,
+    // number of points along each axis
+    size: f32,
+    domain_max: vec3<f32>,
+    // WebGL needs 16 byte alignment
+    padding: f32,
+}
+@group(3) @binding(0)
+var t_lut: texture_2d<f32>;
+@group(3) @binding(1)
+var s_lut: sampler;
+@group(3) @binding(2)
+var<uniform> lut_info: LutInfo;
+
+// the bilinear lookups in the two closest blue slices are interpolated like the third axis of a 3D texture
+fn lut_color(color: vec3<f32>) -> vec3<f32> {
+    let size = lut_info.size;
+    let position = clamp((linear_to_srgb(color) - lut_info.domain_min) / (lut_info.domain_max - lut_info.domain_min), vec3<f32>(0.0), vec3<f32>(1.0)) * (size - 1.0);
+    let slice = floor(position.b);
+    let next_slice = min(slice + 1.0, size - 1.0);
+    let y = (position.g + 0.5) / size;
+    let first = textureSampleLevel(t_lut, s_lut, vec2<f32>((slice * size + position.r + 0.5) / (size * size), y), 0.0).rgb;
+    let second = textureSampleLevel(t_lut, s_lut, vec2<f32>((next_slice * size + position.r + 0.5) / (size * size), y), 0.0).rgb;
+    return srgb_to_linear(mix(first, second, position.b - slice));
+}
@@ -0,0 +1,9 @@
+// declarations of multi pass effects, the `EffectRegistry` includes it into effects of the `MultiPass` kind.
+// Every pass reads the output of the previous pass from `t_diffuse` and the input of the effect from `t_source`,
+// see `effect_passes.rs` for the `@target` and `@pass` annotations.
+#include "prelude.wgsl"
+
+@group(3) @binding(0)
+var t_source: texture_2d<f32>;
+@group(3) @binding(1)
+var s_source: sampler;
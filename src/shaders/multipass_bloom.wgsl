@@ -0,0 +1,75 @@
+// @param threshold f32 min=0 max=1 default=0.7
+// @param intensity f32 min=0 max=4 default=1
+// @param radius f32 min=0 max=4 default=1
+
+// the bright parts of the input are downsampled into smaller and smaller targets,
+// upsampling adds every level to the next larger one and the sum is added to the input
+// @target bloom_1 scale=0.5
+// @target bloom_2 scale=0.25
+// @target bloom_3 scale=0.125
+// @target bloom_4 scale=0.0625
+// @target bloom_5 scale=0.03125
+
+// @pass prefilter output=bloom_1
+// @pass downsample output=bloom_2
+// @pass downsample output=bloom_3
+// @pass downsample output=bloom_4
+// @pass downsample output=bloom_5
+// @pass upsample output=bloom_4 blend=add
+// @pass upsample output=bloom_3 blend=add
+// @pass upsample output=bloom_2 blend=add
+// @pass upsample output=bloom_1 blend=add
+// @pass composite
+
+// number of targets that are added up
+const LEVELS: f32 = 5.0;
+// width of the soft transition around the threshold
+const KNEE: f32 = 0.1;
+
+// average of the 2x2 input pixels below the output pixel
+fn box_filter(uv: vec2<f32>) -> vec4<f32> {
+    let texel = 1.0 / vec2<f32>(textureDimensions(t_diffuse));
+    return (textureSample(t_diffuse, s_diffuse, uv + vec2<f32>(-0.5, -0.5) * texel)
+        + textureSample(t_diffuse, s_diffuse, uv + vec2<f32>(0.5, -0.5) * texel)
+        + textureSample(t_diffuse, s_diffuse, uv + vec2<f32>(-0.5, 0.5) * texel)
+        + textureSample(t_diffuse, s_diffuse, uv + vec2<f32>(0.5, 0.5) * texel)) / 4.0;
+}
+
+@fragment
+fn prefilter(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = box_filter(in.uv).rgb;
+    let brightness = max(color.r, max(color.g, color.b));
+    var soft = clamp(brightness - parameters.threshold + KNEE, 0.0, 2.0 * KNEE);
+    soft = soft * soft / (4.0 * KNEE);
+    let contribution = max(soft, brightness - parameters.threshold) / max(brightness, 0.0001);
+    return vec4<f32>(color * contribution, 1.0);
+}
+
+@fragment
+fn downsample(in: VertexOutput) -> @location(0) vec4<f32> {
+    return box_filter(in.uv);
+}
+
+// 3x3 tent filter, the radius spreads the taps apart
+@fragment
+fn upsample(in: VertexOutput) -> @location(0) vec4<f32> {
+    let offset = parameters.radius / vec2<f32>(textureDimensions(t_diffuse));
+    var sum = vec4<f32>(0.0);
+    for (var y = -1; y <= 1; y++) {
+        for (var x = -1; x <= 1; x++) {
+            let weight = f32((2 - abs(x)) * (2 - abs(y)));
+            sum += weight * textureSample(t_diffuse, s_diffuse, in.uv + vec2<f32>(f32(x), f32(y)) * offset);
+        }
+    }
+    return sum / 16.0;
+}
+
+@fragment
+fn composite(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(t_source, s_source, in.uv);
+    let bloom = textureSample(t_diffuse, s_diffuse, in.uv).rgb / LEVELS;
+    if in.uv.x > (globals.cursor_x + 1) / 2 {
+        return color;
+    }
+    return vec4<f32>(color.rgb + bloom * parameters.intensity, color.a);
+}
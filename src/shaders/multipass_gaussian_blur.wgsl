@@ -0,0 +1,38 @@
+// @param radius f32 min=0 max=32 default=8
+// @param sigma f32 min=0.5 max=16 default=4
+
+// the 2D gaussian kernel is separable, blurring the rows and then the columns needs 2 * (2 * radius + 1)
+// instead of (2 * radius + 1)^2 samples per pixel
+// @target horizontal
+// @pass blur_horizontal output=horizontal
+// @pass blur_vertical
+
+// weighted sum of the pixels on a line through the pixel, `direction` is the distance between two neighboring pixels.
+// `textureSampleLevel` always filters linearly and works with the radius from the uniform in the loop.
+fn blur(uv: vec2<f32>, direction: vec2<f32>) -> vec4<f32> {
+    let radius = i32(round(parameters.radius));
+    var sum = vec4<f32>(0.0);
+    var weights = 0.0;
+    for (var i = -radius; i <= radius; i++) {
+        let x = f32(i);
+        let weight = exp(-x * x / (2.0 * parameters.sigma * parameters.sigma));
+        sum += weight * textureSampleLevel(t_diffuse, s_diffuse, uv + x * direction, 0.0);
+        weights += weight;
+    }
+    return sum / weights;
+}
+
+@fragment
+fn blur_horizontal(in: VertexOutput) -> @location(0) vec4<f32> {
+    let texel = 1.0 / vec2<f32>(textureDimensions(t_diffuse));
+    return blur(in.uv, vec2<f32>(texel.x, 0.0));
+}
+
+@fragment
+fn blur_vertical(in: VertexOutput) -> @location(0) vec4<f32> {
+    if in.uv.x > (globals.cursor_x + 1) / 2 {
+        return textureSampleLevel(t_source, s_source, in.uv, 0.0);
+    }
+    let texel = 1.0 / vec2<f32>(textureDimensions(t_diffuse));
+    return blur(in.uv, vec2<f32>(0.0, texel.y));
+}
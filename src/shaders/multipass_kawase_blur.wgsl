@@ -0,0 +1,62 @@
+// @param offset f32 min=0 max=4 default=1
+
+// dual kawase blur: every level halves the size and spreads the samples further apart,
+// so large radii only cost a few samples on small targets. The offset scales the distance between the samples.
+// @target down_1 scale=0.5
+// @target down_2 scale=0.25
+// @target down_3 scale=0.125
+// @target down_4 scale=0.0625
+// @target up_3 scale=0.125
+// @target up_2 scale=0.25
+// @target up_1 scale=0.5
+
+// @pass downsample input=source output=down_1
+// @pass downsample output=down_2
+// @pass downsample output=down_3
+// @pass downsample output=down_4
+// @pass upsample output=up_3
+// @pass upsample output=up_2
+// @pass upsample output=up_1
+// @pass upsample_final
+
+// `textureSampleLevel` filters linearly when the target is smaller than the input, every sample averages 4 pixels
+fn tap(uv: vec2<f32>, offset: vec2<f32>) -> vec4<f32> {
+    return textureSampleLevel(t_diffuse, s_diffuse, uv + offset, 0.0);
+}
+
+@fragment
+fn downsample(in: VertexOutput) -> @location(0) vec4<f32> {
+    let d = parameters.offset / vec2<f32>(textureDimensions(t_diffuse));
+    let sum = tap(in.uv, vec2<f32>(0.0)) * 4.0
+        + tap(in.uv, vec2<f32>(-d.x, -d.y))
+        + tap(in.uv, vec2<f32>(d.x, -d.y))
+        + tap(in.uv, vec2<f32>(-d.x, d.y))
+        + tap(in.uv, vec2<f32>(d.x, d.y));
+    return sum / 8.0;
+}
+
+fn upsample_color(uv: vec2<f32>) -> vec4<f32> {
+    let d = parameters.offset / vec2<f32>(textureDimensions(t_diffuse));
+    let sum = tap(uv, vec2<f32>(-d.x, 0.0))
+        + tap(uv, vec2<f32>(d.x, 0.0))
+        + tap(uv, vec2<f32>(0.0, -d.y))
+        + tap(uv, vec2<f32>(0.0, d.y))
+        + tap(uv, vec2<f32>(-d.x, -d.y) / 2.0) * 2.0
+        + tap(uv, vec2<f32>(d.x, -d.y) / 2.0) * 2.0
+        + tap(uv, vec2<f32>(-d.x, d.y) / 2.0) * 2.0
+        + tap(uv, vec2<f32>(d.x, d.y) / 2.0) * 2.0;
+    return sum / 12.0;
+}
+
+@fragment
+fn upsample(in: VertexOutput) -> @location(0) vec4<f32> {
+    return upsample_color(in.uv);
+}
+
+@fragment
+fn upsample_final(in: VertexOutput) -> @location(0) vec4<f32> {
+    if in.uv.x > (globals.cursor_x + 1) / 2 {
+        return textureSampleLevel(t_source, s_source, in.uv, 0.0);
+    }
+    return upsample_color(in.uv);
+}
@@ -0,0 +1,14 @@
+// @param strength f32 min=0 max=0.1 default=0.015
+#include "prelude.wgsl"
+
+// shifts the red and blue channels apart like a lens that refracts the colors differently,
+// the shift grows with the distance to the cursor
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    let center = vec2<f32>((globals.cursor_x + 1) / 2, (globals.cursor_y + 1) / 2);
+    let offset = (in.uv - center) * parameters.strength;
+    let red = textureSample(t_diffuse, s_diffuse, in.uv + offset);
+    let green = textureSample(t_diffuse, s_diffuse, in.uv);
+    let blue = textureSample(t_diffuse, s_diffuse, in.uv - offset);
+    return vec4<f32>(red.r, green.g, blue.b, green.a);
+}
@@ -0,0 +1,57 @@
+// @param curvature f32 min=0 max=0.5 default=0.12
+// @param scanline_intensity f32 min=0 max=1 default=0.5
+// @param scanline_size f32 min=1 max=8 default=3
+// @param mask_intensity f32 min=0 max=1 default=0.3
+// @param glow f32 min=0 max=2 default=0.6
+// @param flicker f32 min=0 max=0.3 default=0.05
+#include "prelude.wgsl"
+
+// a cathode ray tube monitor: the picture is bent onto a curved screen, dark gaps separate the scanlines,
+// an aperture grille of red, green and blue stripes covers every output pixel, the glowing phosphor spreads
+// onto the neighboring pixels and a brighter bar rolls down the screen.
+// Scanlines and stripes are sized in output pixels, so they stay sharp at every resolution.
+
+const PI: f32 = 3.14159265;
+// screen heights per second the bar of the flicker rolls down
+const ROLL_SPEED: f32 = 0.15;
+
+// bends the uv coordinates outwards, more the further they are from the center
+fn curve(uv: vec2<f32>) -> vec2<f32> {
+    let centered = uv * 2.0 - 1.0;
+    let curved = centered + centered * centered.yx * centered.yx * parameters.curvature;
+    return curved * 0.5 + 0.5;
+}
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    let is_crt = in.uv.x <= (globals.cursor_x + 1) / 2;
+    let uv = select(in.uv, curve(in.uv), is_crt);
+    let pixel = textureSample(t_diffuse, s_diffuse, uv);
+
+    // the glow blurs the pixel with its neighbors in the distance of two output pixels
+    let spread = 2.0 / vec2<f32>(globals.resolution_x, globals.resolution_y);
+    let neighbors = textureSample(t_diffuse, s_diffuse, uv + vec2<f32>(-1.0, -1.0) * spread)
+        + textureSample(t_diffuse, s_diffuse, uv + vec2<f32>(1.0, -1.0) * spread)
+        + textureSample(t_diffuse, s_diffuse, uv + vec2<f32>(-1.0, 1.0) * spread)
+        + textureSample(t_diffuse, s_diffuse, uv + vec2<f32>(1.0, 1.0) * spread);
+    if !is_crt {
+        return pixel;
+    }
+    var color = pixel.rgb + (pixel.rgb + neighbors.rgb) / 5.0 * parameters.glow;
+
+    // the beam is brightest in the middle of a scanline
+    let scanline = 0.5 + 0.5 * cos(2.0 * PI * uv.y * globals.resolution_y / parameters.scanline_size);
+    color *= mix(1.0, scanline, parameters.scanline_intensity);
+
+    // every output pixel column shows one of the red, green and blue phosphor stripes
+    let stripe = u32(in.clip_position.x) % 3u;
+    let mask = select(vec3<f32>(1.0 - parameters.mask_intensity), vec3<f32>(1.0), vec3<u32>(0u, 1u, 2u) == vec3<u32>(stripe));
+    color *= mask;
+
+    let roll = fract(uv.y - globals.time * ROLL_SPEED);
+    color *= 1.0 - parameters.flicker * roll;
+
+    // the screen ends where the curvature moves the picture out of the texture
+    let outside = any(uv < vec2<f32>(0.0)) || any(uv > vec2<f32>(1.0));
+    return select(vec4<f32>(color, pixel.a), vec4<f32>(0.0, 0.0, 0.0, 1.0), outside);
+}
@@ -0,0 +1,18 @@
+// @param thickness f32 min=0.5 max=4 default=1
+// @param threshold f32 min=0 max=1 default=0.2
+// @param color vec3f default=1,1,1
+// @param image f32 min=0 max=1 default=0
+#include "prelude.wgsl"
+#include "edge.wgsl"
+
+// edges after the Sobel operator, which weights the direct neighbors twice as much as the diagonal ones.
+// The lines are drawn in `color` over the input, which is darkened to the brightness `image`.
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    let pixel = textureSample(t_diffuse, s_diffuse, in.uv);
+    if in.uv.x > (globals.cursor_x + 1) / 2 {
+        return pixel;
+    }
+    let edge = edge_mask(sobel(in.uv, parameters.thickness), parameters.threshold);
+    return vec4<f32>(mix(pixel.rgb * parameters.image, parameters.color, edge), pixel.a);
+}
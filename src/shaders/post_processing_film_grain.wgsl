@@ -0,0 +1,29 @@
+// @param intensity f32 min=0 max=1 default=0.15
+// @param size f32 min=1 max=8 default=1.5
+// @param speed f32 min=0 max=60 default=24
+// @param seed f32 min=0 max=1000 default=0
+#include "prelude.wgsl"
+#include "color.wgsl"
+
+// noise that changes `speed` times per second like the grain of a film projected at 24 frames per second.
+// The seed selects a different pattern, e.g. for several instances of the effect.
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    let pixel = textureSample(t_diffuse, s_diffuse, in.uv);
+    if in.uv.x > (globals.cursor_x + 1) / 2 {
+        return pixel;
+    }
+    let grain_position = vec2<u32>(in.clip_position.xy / parameters.size);
+    let grain_frame = u32(globals.time * parameters.speed) + u32(parameters.seed) * 7919u;
+    let noise = f32(hash(grain_position.x + hash(grain_position.y + hash(grain_frame)))) / 4294967295.0;
+    // the grain is added to the encoded color, so it is equally visible in dark and bright areas
+    let grainy = linear_to_srgb(pixel.rgb) + (noise - 0.5) * parameters.intensity;
+    return vec4<f32>(srgb_to_linear(clamp(grainy, vec3<f32>(0.0), vec3<f32>(1.0))), pixel.a);
+}
+
+// PCG hash, same as the seed of `Globals::next_frame`
+fn hash(value: u32) -> u32 {
+    let state = value * 747796405u + 2891336453u;
+    let word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+    return (word >> 22u) ^ word;
+}
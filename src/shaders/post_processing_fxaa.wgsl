@@ -0,0 +1,142 @@
+// @param subpixel f32 min=0 max=1 default=0.75
+// @param edge_threshold f32 min=0.063 max=0.333 default=0.125
+// @param edge_threshold_min f32 min=0 max=0.1 default=0.0312
+#include "prelude.wgsl"
+
+// fast approximate anti-aliasing after FXAA 3.11 by Timothy Lottes: finds the direction of an edge through the pixel,
+// walks along the edge to both of its ends and blends the pixel with its neighbor across the edge depending on the
+// position between the ends. Works best as the last effect of a chain, after the effects that create the hard edges.
+
+// the walk along the edge stops after this many samples in each direction
+const ITERATIONS: i32 = 12;
+
+// perceived brightness, the square root approximates the sRGB encoding of the linear color
+fn luma(color: vec3<f32>) -> f32 {
+    return sqrt(dot(color, vec3<f32>(0.299, 0.587, 0.114)));
+}
+
+// `textureSampleLevel` because the samples depend on the branches, it filters linearly like `textureSample`
+fn luma_at(uv: vec2<f32>) -> f32 {
+    return luma(textureSampleLevel(t_diffuse, s_diffuse, uv, 0.0).rgb);
+}
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSampleLevel(t_diffuse, s_diffuse, in.uv, 0.0);
+    if in.uv.x > (globals.cursor_x + 1) / 2 {
+        return color;
+    }
+
+    let texel = 1.0 / vec2<f32>(textureDimensions(t_diffuse));
+    let luma_center = luma(color.rgb);
+    let luma_down = luma_at(in.uv + vec2<f32>(0.0, -1.0) * texel);
+    let luma_up = luma_at(in.uv + vec2<f32>(0.0, 1.0) * texel);
+    let luma_left = luma_at(in.uv + vec2<f32>(-1.0, 0.0) * texel);
+    let luma_right = luma_at(in.uv + vec2<f32>(1.0, 0.0) * texel);
+
+    // pixels without contrast to their neighbors are not on an edge
+    let luma_min = min(luma_center, min(min(luma_down, luma_up), min(luma_left, luma_right)));
+    let luma_max = max(luma_center, max(max(luma_down, luma_up), max(luma_left, luma_right)));
+    let luma_range = luma_max - luma_min;
+    if luma_range < max(parameters.edge_threshold_min, luma_max * parameters.edge_threshold) {
+        return color;
+    }
+
+    let luma_down_left = luma_at(in.uv + vec2<f32>(-1.0, -1.0) * texel);
+    let luma_up_right = luma_at(in.uv + vec2<f32>(1.0, 1.0) * texel);
+    let luma_up_left = luma_at(in.uv + vec2<f32>(-1.0, 1.0) * texel);
+    let luma_down_right = luma_at(in.uv + vec2<f32>(1.0, -1.0) * texel);
+
+    let luma_down_up = luma_down + luma_up;
+    let luma_left_right = luma_left + luma_right;
+    let luma_left_corners = luma_down_left + luma_up_left;
+    let luma_down_corners = luma_down_left + luma_down_right;
+    let luma_right_corners = luma_down_right + luma_up_right;
+    let luma_up_corners = luma_up_right + luma_up_left;
+
+    let edge_horizontal = abs(-2.0 * luma_left + luma_left_corners) + abs(-2.0 * luma_center + luma_down_up) * 2.0
+        + abs(-2.0 * luma_right + luma_right_corners);
+    let edge_vertical = abs(-2.0 * luma_up + luma_up_corners) + abs(-2.0 * luma_center + luma_left_right) * 2.0
+        + abs(-2.0 * luma_down + luma_down_corners);
+    let is_horizontal = edge_horizontal >= edge_vertical;
+
+    // the neighbor across the edge is on the side with the steeper gradient
+    let luma_1 = select(luma_left, luma_down, is_horizontal);
+    let luma_2 = select(luma_right, luma_up, is_horizontal);
+    let gradient_1 = luma_1 - luma_center;
+    let gradient_2 = luma_2 - luma_center;
+    let is_1_steepest = abs(gradient_1) >= abs(gradient_2);
+    let gradient_scaled = 0.25 * max(abs(gradient_1), abs(gradient_2));
+
+    var step_length = select(texel.x, texel.y, is_horizontal);
+    var luma_local_average = 0.5 * (luma_2 + luma_center);
+    if is_1_steepest {
+        step_length = -step_length;
+        luma_local_average = 0.5 * (luma_1 + luma_center);
+    }
+
+    // start on the edge between the pixel and its neighbor and walk along the edge in both directions
+    var current_uv = in.uv;
+    if is_horizontal {
+        current_uv.y += step_length * 0.5;
+    } else {
+        current_uv.x += step_length * 0.5;
+    }
+    let offset = select(vec2<f32>(0.0, texel.y), vec2<f32>(texel.x, 0.0), is_horizontal);
+    // the steps get longer the further the walk goes
+    var quality = array<f32, 12>(1.0, 1.0, 1.0, 1.0, 1.0, 1.5, 2.0, 2.0, 2.0, 2.0, 4.0, 8.0);
+
+    var uv_1 = current_uv - offset;
+    var uv_2 = current_uv + offset;
+    var luma_end_1 = luma_at(uv_1) - luma_local_average;
+    var luma_end_2 = luma_at(uv_2) - luma_local_average;
+    var reached_1 = abs(luma_end_1) >= gradient_scaled;
+    var reached_2 = abs(luma_end_2) >= gradient_scaled;
+    if !reached_1 {
+        uv_1 -= offset;
+    }
+    if !reached_2 {
+        uv_2 += offset;
+    }
+
+    for (var i = 2; i < ITERATIONS && !(reached_1 && reached_2); i++) {
+        if !reached_1 {
+            luma_end_1 = luma_at(uv_1) - luma_local_average;
+            reached_1 = abs(luma_end_1) >= gradient_scaled;
+            if !reached_1 {
+                uv_1 -= offset * quality[i];
+            }
+        }
+        if !reached_2 {
+            luma_end_2 = luma_at(uv_2) - luma_local_average;
+            reached_2 = abs(luma_end_2) >= gradient_scaled;
+            if !reached_2 {
+                uv_2 += offset * quality[i];
+            }
+        }
+    }
+
+    // the closer end of the edge decides how far the pixel is blended with its neighbor
+    let distance_1 = select(in.uv.y - uv_1.y, in.uv.x - uv_1.x, is_horizontal);
+    let distance_2 = select(uv_2.y - in.uv.y, uv_2.x - in.uv.x, is_horizontal);
+    let is_direction_1 = distance_1 < distance_2;
+    let pixel_offset = -min(distance_1, distance_2) / (distance_1 + distance_2) + 0.5;
+    // only blend if the luma at the closer end varies in the same direction as the pixel
+    let is_luma_center_smaller = luma_center < luma_local_average;
+    let correct_variation = (select(luma_end_2, luma_end_1, is_direction_1) < 0.0) != is_luma_center_smaller;
+    var final_offset = select(0.0, pixel_offset, correct_variation);
+
+    // single pixels that are brighter or darker than their surrounding are blended as well
+    let luma_average = (2.0 * (luma_down_up + luma_left_right) + luma_left_corners + luma_right_corners) / 12.0;
+    let subpixel_offset = clamp(abs(luma_average - luma_center) / luma_range, 0.0, 1.0);
+    let subpixel_offset_smooth = (-2.0 * subpixel_offset + 3.0) * subpixel_offset * subpixel_offset;
+    final_offset = max(final_offset, subpixel_offset_smooth * subpixel_offset_smooth * parameters.subpixel);
+
+    var final_uv = in.uv;
+    if is_horizontal {
+        final_uv.y += final_offset * step_length;
+    } else {
+        final_uv.x += final_offset * step_length;
+    }
+    return vec4<f32>(textureSampleLevel(t_diffuse, s_diffuse, final_uv, 0.0).rgb, color.a);
+}
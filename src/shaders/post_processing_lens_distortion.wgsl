@@ -0,0 +1,21 @@
+// @param k1 f32 min=-1 max=1 default=0.2
+// @param k2 f32 min=-1 max=1 default=0.05
+// @param zoom f32 min=0.5 max=2 default=1
+#include "prelude.wgsl"
+
+// radial lens distortion after Brown-Conrady: positive coefficients bend the image like a barrel,
+// negative ones like a pincushion. Zoom in to hide the border of barrel distortions.
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    // the distortion is round on every surface and the distance to the center is 1 in the corners
+    let aspect_scale = vec2<f32>(globals.aspect, 1.0) / length(0.5 * vec2<f32>(globals.aspect, 1.0));
+    let position = (in.uv - 0.5) * aspect_scale;
+    let r2 = dot(position, position);
+    let factor = 1.0 + parameters.k1 * r2 + parameters.k2 * r2 * r2;
+    let distorted_uv = position * factor / parameters.zoom / aspect_scale + 0.5;
+    let distorted_uv_or_uv = select(in.uv, distorted_uv, in.uv.x <= (globals.cursor_x + 1) / 2);
+    let pixel = textureSample(t_diffuse, s_diffuse, distorted_uv_or_uv);
+
+    let outside = any(distorted_uv_or_uv < vec2<f32>(0.0)) || any(distorted_uv_or_uv > vec2<f32>(1.0));
+    return select(pixel, vec4<f32>(0.0, 0.0, 0.0, 1.0), outside);
+}
@@ -0,0 +1,24 @@
+// @param levels f32 min=2 max=8 default=4
+// @param thickness f32 min=0.5 max=4 default=1.5
+// @param threshold f32 min=0 max=1 default=0.25
+// @param color vec3f default=0,0,0
+#include "prelude.wgsl"
+#include "edge.wgsl"
+
+// cel shading: the brightness is reduced to a few flat bands and the Sobel edges are drawn as outlines in `color`
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    let pixel = textureSample(t_diffuse, s_diffuse, in.uv);
+    if in.uv.x > (globals.cursor_x + 1) / 2 {
+        return pixel;
+    }
+    // the bands are quantized in sRGB so they look evenly spaced, the hue is kept
+    let srgb = linear_to_srgb(pixel.rgb);
+    let luma = dot(srgb, vec3<f32>(0.299, 0.587, 0.114));
+    let steps = floor(parameters.levels) - 1.0;
+    let banded_luma = round(luma * steps) / steps;
+    let banded = srgb_to_linear(clamp(srgb * banded_luma / max(luma, 0.0001), vec3<f32>(0.0), vec3<f32>(1.0)));
+
+    let edge = edge_mask(sobel(in.uv, parameters.thickness), parameters.threshold);
+    return vec4<f32>(mix(banded, parameters.color, edge), pixel.a);
+}
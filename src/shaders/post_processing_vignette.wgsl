@@ -0,0 +1,18 @@
+// @param intensity f32 min=0 max=1 default=0.6
+// @param radius f32 min=0 max=1.5 default=0.75
+// @param softness f32 min=0.01 max=1 default=0.45
+#include "prelude.wgsl"
+
+// darkens the image towards the corners, the vignette is round on every surface
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    let pixel = textureSample(t_diffuse, s_diffuse, in.uv);
+    if in.uv.x > (globals.cursor_x + 1) / 2 {
+        return pixel;
+    }
+    // the distance to the center is 1 in the corners
+    let aspect_scale = vec2<f32>(globals.aspect, 1.0);
+    let distance = length((in.uv - 0.5) * aspect_scale) / length(0.5 * aspect_scale);
+    let darkening = smoothstep(parameters.radius - parameters.softness, parameters.radius, distance);
+    return vec4<f32>(pixel.rgb * (1.0 - darkening * parameters.intensity), pixel.a);
+}
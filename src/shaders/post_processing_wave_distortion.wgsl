@@ -1,58 +1,13 @@
-const PI: f32 = 3.14159265;
-
-struct Globals {
-    time: f32,
-    cursor_x: f32,
-    cursor_y: f32,
-    // WebGL needs 16 byte alignment 
-    padding: f32,
-}
-@group(0) @binding(0)
-var<uniform> globals: Globals;
-
-struct VertexOutput {
-    @builtin(position) clip_position: vec4<f32>,
-    @location(0) uv: vec2<f32>,
-}
-
-@vertex
-fn vertex(
-    @builtin(vertex_index) id: u32,
-) -> VertexOutput {
-    // vertices describe a rectangle that covers the complete screen
-    var pos = array(
-        vec2f(1.0, 1.0),
-        vec2f(-1.0, 1.0),
-        vec2f(-1.0, -1.0),
-        vec2f(1.0, 1.0),
-        vec2f(-1.0, -1.0),
-        vec2f(1.0, -1.0),
-    );
+// @param amplitude f32 min=0 max=0.1 default=0.01
+// @param frequency f32 min=0 max=100 default=20
+// @param speed f32 min=-50 max=50 default=5
+#include "prelude.wgsl"
 
-    // provide a texture mapping that covers the rectangle created above
-    var uv = array(
-        vec2f(1.0, 0.0),
-        vec2f(0.0, 0.0),
-        vec2f(0.0, 1.0),
-        vec2f(1.0, 0.0),
-        vec2f(0.0, 1.0),
-        vec2f(1.0, 1.0),
-    );
-
-    var out: VertexOutput;
-    out.clip_position = vec4<f32>(pos[id], 0.0, 1.0);
-    out.uv = vec2<f32>(uv[id]);
-    return out;
-}
-
-@group(1) @binding(0)
-var t_diffuse: texture_2d<f32>;
-@group(1) @binding(1)
-var s_diffuse: sampler;
+const PI: f32 = 3.14159265;
 
 @fragment
 fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
-    let x_sin = sin(in.uv.y * 20 + globals.time * 5) / 100.;
+    let x_sin = sin(in.uv.y * parameters.frequency + globals.time * parameters.speed) * parameters.amplitude;
     let x = x_sin * step(in.uv.x + x_sin, (globals.cursor_x + 1) / 2);
     let tex_x = in.uv.x + x;
     return textureSample(t_diffuse, s_diffuse, vec2<f32>(tex_x, in.uv.y));
@@ -0,0 +1,77 @@
+// shared declarations of all shaders, include them with `#include "prelude.wgsl"`
+
+// has to match `Globals` in shader_globals.rs
+struct Globals {
+    // seconds since the start
+    time: f32,
+    // cursor position from -1 to 1, (-1, -1) is the top left corner
+    cursor_x: f32,
+    cursor_y: f32,
+    // seconds since the previous frame
+    delta_time: f32,
+    // size of the surface in pixels
+    resolution_x: f32,
+    resolution_y: f32,
+    // width divided by height
+    aspect: f32,
+    frame: u32,
+    // pressed buttons, see the BUTTON_ constants
+    buttons: u32,
+    // random number that changes every frame
+    seed: u32,
+    // WebGL needs 16 byte alignment
+    padding_0: u32,
+    padding_1: u32,
+}
+@group(0) @binding(0)
+var<uniform> globals: Globals;
+
+const BUTTON_LEFT: u32 = 1u;
+const BUTTON_RIGHT: u32 = 2u;
+const BUTTON_MIDDLE: u32 = 4u;
+const BUTTON_TOUCH: u32 = 8u;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+@vertex
+fn vertex(
+    @builtin(vertex_index) id: u32,
+) -> VertexOutput {
+    // vertices describe a rectangle that covers the complete screen
+    var pos = array(
+        vec2f(1.0, 1.0),
+        vec2f(-1.0, 1.0),
+        vec2f(-1.0, -1.0),
+        vec2f(1.0, 1.0),
+        vec2f(-1.0, -1.0),
+        vec2f(1.0, -1.0),
+    );
+
+    // provide a texture mapping that covers the rectangle created above
+    var uv = array(
+        vec2f(1.0, 0.0),
+        vec2f(0.0, 0.0),
+        vec2f(0.0, 1.0),
+        vec2f(1.0, 0.0),
+        vec2f(0.0, 1.0),
+        vec2f(1.0, 1.0),
+    );
+
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(pos[id], 0.0, 1.0);
+    out.uv = vec2<f32>(uv[id]);
+    return out;
+}
+
+@group(1) @binding(0)
+var t_diffuse: texture_2d<f32>;
+@group(1) @binding(1)
+var s_diffuse: sampler;
+
+// size of one pixel of the input texture in uv coordinates, for sampling the neighbors of a pixel
+fn texel_size() -> vec2<f32> {
+    return 1.0 / vec2<f32>(textureDimensions(t_diffuse));
+}
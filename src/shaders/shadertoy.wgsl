@@ -0,0 +1,34 @@
+// wraps Shadertoy code that was translated to WGSL, the `EffectRegistry` includes it into effects of the `Shadertoy` kind.
+// `void mainImage(out vec4 fragColor, in vec2 fragCoord)` becomes `fn mainImage(fragCoord: vec2<f32>) -> vec4<f32>`
+// and `texture(iChannel0, uv)` becomes `iChannel0(uv)`.
+#include "prelude.wgsl"
+
+// viewport size in pixels, z is the pixel aspect ratio
+var<private> iResolution: vec3<f32>;
+// seconds since the start
+var<private> iTime: f32;
+// seconds since the previous frame
+var<private> iTimeDelta: f32;
+var<private> iFrame: i32;
+// cursor position in pixels, zw is the cursor position too while the left button or a touch is down and 0 otherwise
+var<private> iMouse: vec4<f32>;
+
+// the input texture, Shadertoy has the origin in the bottom left corner.
+// Sampling the base level keeps it usable in non-uniform control flow like GLSL `texture`, the render targets have no mips.
+fn iChannel0(uv: vec2<f32>) -> vec4<f32> {
+    return textureSampleLevel(t_diffuse, s_diffuse, vec2<f32>(uv.x, 1.0 - uv.y), 0.0);
+}
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    iResolution = vec3<f32>(globals.resolution_x, globals.resolution_y, 1.0);
+    iTime = globals.time;
+    iTimeDelta = globals.delta_time;
+    iFrame = i32(globals.frame);
+    let cursor = vec2<f32>((globals.cursor_x + 1.0) / 2.0, (1.0 - globals.cursor_y) / 2.0) * iResolution.xy;
+    let pressed = (globals.buttons & (BUTTON_LEFT | BUTTON_TOUCH)) != 0u;
+    iMouse = vec4<f32>(cursor, select(vec2<f32>(0.0), cursor, pressed));
+
+    let frag_coord = vec2<f32>(in.clip_position.x, iResolution.y - in.clip_position.y);
+    return mainImage(frag_coord);
+}
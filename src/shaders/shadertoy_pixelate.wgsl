@@ -0,0 +1,11 @@
+// @param block_size f32 min=1 max=64 default=8
+
+// Shadertoy style effect, pixelates a pulsing circle around the mouse
+fn mainImage(fragCoord: vec2<f32>) -> vec4<f32> {
+    let radius = (0.3 + 0.1 * sin(iTime * 2.0)) * iResolution.y;
+    let block = (floor(fragCoord / parameters.block_size) + 0.5) * parameters.block_size;
+
+    let color = iChannel0(fragCoord / iResolution.xy);
+    let pixelated = iChannel0(block / iResolution.xy);
+    return select(color, pixelated, distance(fragCoord, iMouse.xy) < radius);
+}
@@ -1,7 +1,6 @@
 use image::GenericImageView;
 
 pub struct Texture {
-    #[allow(unused)]
     pub texture: wgpu::Texture,
     pub view: wgpu::TextureView,
     pub sampler: wgpu::Sampler,
@@ -22,13 +21,50 @@ impl Texture {
             height: dimensions.1,
             depth_or_array_layers: 1,
         };
+        Self::create(
+            device,
+            queue,
+            &rgba,
+            size,
+            wgpu::TextureDimension::D2,
+            wgpu::TextureFormat::Rgba8UnormSrgb,
+            wgpu::FilterMode::Nearest,
+            label,
+        )
+    }
+
+    /// creates a 2D or 3D texture from values that are not colors, e.g. a lookup table.
+    /// The values are read without sRGB conversion and filtered linearly.
+    pub fn from_data(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        data: &[u8],
+        size: wgpu::Extent3d,
+        dimension: wgpu::TextureDimension,
+        format: wgpu::TextureFormat,
+        label: &str,
+    ) -> Self {
+        Self::create(device, queue, data, size, dimension, format, wgpu::FilterMode::Linear, Some(label))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn create(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        data: &[u8],
+        size: wgpu::Extent3d,
+        dimension: wgpu::TextureDimension,
+        format: wgpu::TextureFormat,
+        min_filter: wgpu::FilterMode,
+        label: Option<&str>,
+    ) -> Self {
         let texture = device.create_texture(&wgpu::TextureDescriptor {
             label,
             size,
             mip_level_count: 1,
             sample_count: 1,
-            dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            dimension,
+            format,
             usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
             view_formats: &[],
         });
@@ -40,11 +76,11 @@ impl Texture {
                 mip_level: 0,
                 origin: wgpu::Origin3d::ZERO,
             },
-            &rgba,
+            data,
             wgpu::ImageDataLayout {
                 offset: 0,
-                bytes_per_row: Some(4 * dimensions.0),
-                rows_per_image: Some(dimensions.1),
+                bytes_per_row: format.block_copy_size(None).map(|block_size| block_size * size.width),
+                rows_per_image: Some(size.height),
             },
             size,
         );
@@ -55,7 +91,7 @@ impl Texture {
             address_mode_v: wgpu::AddressMode::ClampToEdge,
             address_mode_w: wgpu::AddressMode::ClampToEdge,
             mag_filter: wgpu::FilterMode::Linear,
-            min_filter: wgpu::FilterMode::Nearest,
+            min_filter,
             mipmap_filter: wgpu::FilterMode::Nearest,
             ..Default::default()
         });
@@ -0,0 +1,111 @@
+//! parses config files without opening a window
+
+use shader_post_processing_example::config::{Config, ConfigError, ParameterValue};
+use shader_post_processing_example::EffectRegistry;
+use std::path::PathBuf;
+
+#[test]
+fn parses_all_keys() {
+    let source = r#"
+# a look for the demo
+scene = "photo.png"
+effects = ["wave_distortion", "droplet"] # applied in order
+sampler = "nearest"
+presets = "looks"
+
+[window]
+width = 1_280
+height = 720
+
+[parameters.droplet]
+fall_off_distance = 0.4
+"#;
+    let config = Config::parse(source, &EffectRegistry::built_in()).expect("the config is valid");
+    assert_eq!(
+        config,
+        Config {
+            scene: Some(PathBuf::from("photo.png")),
+            effects: vec!["wave_distortion".to_string(), "droplet".to_string()],
+            parameters: vec![ParameterValue {
+                effect: "droplet".to_string(),
+                name: "fall_off_distance".to_string(),
+                values: vec![0.4],
+            }],
+            sampler: Some(wgpu::FilterMode::Nearest),
+            window_size: Some((1280, 720)),
+            presets: Some(PathBuf::from("looks")),
+        }
+    );
+}
+
+#[test]
+fn errors_point_to_the_key() {
+    let error = |source: &str| match Config::parse(source, &EffectRegistry::built_in()) {
+        Err(ConfigError::Invalid { line, key, .. }) => (line, key),
+        result => panic!("expected an invalid config but got {:?}", result),
+    };
+
+    assert_eq!(error("effects = [\"droplet\", \"blur_everything\"]"), (1, "effects[1]".to_string()));
+    assert_eq!(error("\n[window]\nwidth = 0\nheight = 220"), (3, "window.width".to_string()));
+    assert_eq!(error("[window]\nwidth = 800"), (1, "window.height".to_string()));
+    assert_eq!(
+        error("[parameters.droplet]\nstrenght = 1"),
+        (2, "parameters.droplet.strenght".to_string())
+    );
+    assert_eq!(error("[parameters.blur_everything]"), (1, "parameters.blur_everything".to_string()));
+    assert_eq!(error("sampler = \"cubic\""), (1, "sampler".to_string()));
+    assert_eq!(error("scene = 'a.png'\nscene = 'b.png'"), (2, "scene".to_string()));
+    assert_eq!(error("window_size = 800"), (1, "window_size".to_string()));
+    assert_eq!(error("sampler = true"), (1, "sampler".to_string()));
+    assert_eq!(error("[window]\nwidth = 800.0\nheight = 220"), (2, "window.width".to_string()));
+    assert_eq!(error("\nwindow = { width = 800 }"), (2, "window.height".to_string()));
+    assert_eq!(
+        error("effects = [\n    \"droplet\",\n    \"blur_everything\",\n]"),
+        (3, "effects[1]".to_string())
+    );
+    assert_eq!(
+        error("[parameters.droplet]\nfall_off_distance = nan"),
+        (2, "parameters.droplet.fall_off_distance".to_string())
+    );
+    assert_eq!(
+        error("parameters.droplet.fall_off_distance = [0.4, inf]"),
+        (1, "parameters.droplet.fall_off_distance".to_string())
+    );
+    assert_eq!(error("[window]\nwidth = 800\nheight = 220 220"), (3, "height".to_string()));
+}
+
+#[test]
+fn parses_toml_syntax() {
+    let source = r#"
+effects = [
+    "wave_distortion", # moves the pixels
+    "droplet",
+]
+window = { width = 800, height = 220 }
+parameters.droplet."fall_off_distance" = 0.4
+"#;
+    let config = Config::parse(source, &EffectRegistry::built_in()).expect("the config is valid");
+    assert_eq!(config.effects, ["wave_distortion", "droplet"]);
+    assert_eq!(config.window_size, Some((800, 220)));
+    assert_eq!(
+        config.parameters,
+        [ParameterValue {
+            effect: "droplet".to_string(),
+            name: "fall_off_distance".to_string(),
+            values: vec![0.4],
+        }]
+    );
+}
+
+#[test]
+fn effects_of_the_registry_are_accepted() {
+    let source = "effects = [\"fill\"]\n\n[parameters.fill]\nred = 1";
+    assert!(Config::parse(source, &EffectRegistry::built_in()).is_err());
+
+    let mut effect_registry = EffectRegistry::built_in();
+    effect_registry.register_name("fill");
+    let config = Config::parse(source, &effect_registry).expect("the effect is registered");
+    assert_eq!(config.effects, ["fill"]);
+    // the parameters of effects that are implemented in Rust are checked when they are set
+    assert_eq!(config.parameters[0].name, "red");
+}
@@ -0,0 +1,100 @@
+//! an effect that implements `Effect` outside of the crate and is applied in a chain with the shader effects
+
+use shader_post_processing_example::effect::Effect;
+use shader_post_processing_example::headless::HeadlessRenderer;
+use shader_post_processing_example::Globals;
+
+const WIDTH: u32 = 8;
+const HEIGHT: u32 = 4;
+
+/// fills the destination with red, the brightness of the red is the time of the last `update`
+struct Fill {
+    red: f64,
+}
+
+impl Effect for Fill {
+    fn resize(&mut self, _device: &wgpu::Device, _size: wgpu::Extent3d) {}
+
+    fn update(&mut self, _queue: &wgpu::Queue, globals: &Globals) {
+        self.red = globals.time as f64;
+    }
+
+    fn encode(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        _src_bind_group: &wgpu::BindGroup,
+        dst_view: &wgpu::TextureView,
+        _globals_bind_group: &wgpu::BindGroup,
+    ) {
+        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("fill"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: dst_view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color {
+                        r: self.red,
+                        g: 0.0,
+                        b: 0.0,
+                        a: 1.0,
+                    }),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+    }
+}
+
+fn renderer() -> HeadlessRenderer {
+    pollster::block_on(HeadlessRenderer::new(WIDTH, HEIGHT, true)).expect("the custom effect tests need the fallback adapter")
+}
+
+/// the effects are applied to the whole frame
+fn globals() -> Globals {
+    let mut globals = Globals::new();
+    globals.time = 1.0;
+    globals.cursor_x = 1.0;
+    globals
+}
+
+#[test]
+fn custom_effects_are_applied_in_the_chain() {
+    let mut renderer = renderer();
+    let fill = renderer.add_effect("fill", Box::new(Fill { red: 0.0 }));
+    assert_eq!(renderer.effect_index("fill"), Some(fill));
+    assert_eq!(renderer.effect_names().nth(fill), Some("fill"));
+
+    let frame = renderer.render(&[fill], &globals()).expect("rendering failed");
+    assert!(
+        frame.pixels().all(|pixel| pixel.0 == [255, 0, 0, 255]),
+        "{:?}",
+        frame.get_pixel(0, 0)
+    );
+
+    // the shader effect reads the output of the custom effect
+    let invert_color = renderer.effect_index("invert_color").expect("built in effect");
+    let frame = renderer.render(&[fill, invert_color], &globals()).expect("rendering failed");
+    assert!(
+        frame.pixels().all(|pixel| pixel.0 == [0, 255, 255, 255]),
+        "{:?}",
+        frame.get_pixel(0, 0)
+    );
+}
+
+#[test]
+fn custom_effects_replace_effects_with_the_same_name() {
+    let mut renderer = renderer();
+    let effect_count = renderer.effect_count();
+    let invert_color = renderer.effect_index("invert_color").expect("built in effect");
+
+    assert_eq!(renderer.add_effect("invert_color", Box::new(Fill { red: 0.0 })), invert_color);
+    assert_eq!(renderer.effect_count(), effect_count);
+    // the replaced effect has no parameters
+    assert!(renderer.parameters(invert_color).is_none());
+
+    let frame = renderer.render(&[invert_color], &globals()).expect("rendering failed");
+    assert_eq!(frame.get_pixel(0, 0).0, [255, 0, 0, 255]);
+}
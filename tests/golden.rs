@@ -0,0 +1,201 @@
+//! renders the scene through every post processing effect on the software adapter and compares the frames
+//! with the reference images in `tests/golden`.
+//!
+//! Run with `UPDATE_GOLDEN=1 cargo test --test golden` to replace the reference images after an intended change.
+//! Failing frames and their diff images are written to `target/tmp/golden`.
+
+use shader_post_processing_example::headless::{HeadlessRenderer, TEXTURE_FORMAT};
+use shader_post_processing_example::scene::ProceduralScene;
+use shader_post_processing_example::Globals;
+use std::path::{Path, PathBuf};
+
+const WIDTH: u32 = 400;
+const HEIGHT: u32 = 110;
+/// software rasterizers differ slightly in filtering and rounding between versions and platforms
+const CHANNEL_TOLERANCE: u8 = 8;
+/// share of pixels that may differ by more than `CHANNEL_TOLERANCE`
+const MAX_DIFFERENT_PIXELS: f64 = 0.002;
+
+/// the edge effects render this scene instead of the image, its hard edges would look the same for every kernel.
+/// The kernels differ on gradients, curves and anti-aliased diagonal lines.
+const EDGE_SCENE: &str = r#"
+#include "prelude.wgsl"
+#include "color.wgsl"
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    let position = in.clip_position.xy;
+    var brightness = position.x / 400.0 * 0.5 + position.y / 110.0 * 0.2;
+    // a soft blob with a blurred rim and a circle with an anti-aliased edge
+    brightness += 0.5 * exp(-pow(distance(position, vec2<f32>(80.0, 55.0)) / 30.0, 2.0));
+    brightness = mix(brightness, 0.9, 1.0 - smoothstep(38.0, 44.0, distance(position, vec2<f32>(200.0, 55.0))));
+    brightness = mix(brightness, 0.05, 1.0 - smoothstep(20.0, 21.5, distance(position, vec2<f32>(200.0, 55.0))));
+    // a diagonal line that is one pixel wide
+    let line = abs(position.x - 260.0 - position.y * 0.7) / sqrt(1.49);
+    brightness = mix(brightness, 1.0, 1.0 - smoothstep(0.5, 1.5, line));
+    return vec4<f32>(srgb_to_linear(vec3<f32>(brightness, brightness * 0.9, brightness * 0.8)), 1.0);
+}
+"#;
+
+struct Case {
+    name: String,
+    effect_indices: Vec<usize>,
+}
+
+#[test]
+fn effects_match_golden_images() {
+    let mut renderer = pollster::block_on(HeadlessRenderer::new(WIDTH, HEIGHT, true)).expect("the golden tests need the fallback adapter");
+
+    // fixed values, the animated effects would differ on every run otherwise
+    let mut globals = Globals::new();
+    globals.time = 1.5;
+    globals.cursor_x = 0.25;
+    globals.cursor_y = -0.5;
+
+    let mut cases = vec![Case {
+        name: "scene".to_string(),
+        effect_indices: Vec::new(),
+    }];
+    let mut edge_cases = vec![Case {
+        name: "edge_scene".to_string(),
+        effect_indices: Vec::new(),
+    }];
+    for (effect_index, name) in renderer.effect_names().enumerate() {
+        let case = Case {
+            name: name.to_string(),
+            effect_indices: vec![effect_index],
+        };
+        match name.starts_with("edge_") {
+            true => edge_cases.push(case),
+            false => cases.push(case),
+        }
+    }
+    let stacked = ["wave_distortion", "invert_color", "droplet"];
+    cases.push(Case {
+        name: "stacked".to_string(),
+        effect_indices: stacked
+            .iter()
+            .map(|name| renderer.effect_index(name).expect("built in effect"))
+            .collect(),
+    });
+
+    let mut failures = Vec::new();
+    for case in &cases {
+        check(&mut renderer, case, &globals, &mut failures);
+    }
+
+    let edge_scene = ProceduralScene::new(
+        renderer.device(),
+        TEXTURE_FORMAT,
+        renderer.globals_bind_group_layout(),
+        "edge_scene.wgsl",
+        EDGE_SCENE,
+    )
+    .expect("the includes of the edge scene exist");
+    renderer.set_scene(Box::new(edge_scene));
+    let edge_frames: Vec<_> = edge_cases
+        .iter()
+        .map(|case| (case, check(&mut renderer, case, &globals, &mut failures)))
+        .collect();
+    // identical references would not notice if one kernel was replaced by another, the first case is the scene itself
+    for (index, (case, frame)) in edge_frames.iter().enumerate().skip(1) {
+        for (other_case, other_frame) in &edge_frames[index + 1..] {
+            if compare(frame, other_frame).is_ok() {
+                failures.push(format!("{} and {} look the same on the edge scene", case.name, other_case.name));
+            }
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "frames differ from the golden images:\n{}",
+        failures.join("\n")
+    );
+}
+
+/// renders the case and compares it with its reference image, or replaces the reference image with `UPDATE_GOLDEN`
+fn check(renderer: &mut HeadlessRenderer, case: &Case, globals: &Globals, failures: &mut Vec<String>) -> image::RgbaImage {
+    let frame = renderer.render(&case.effect_indices, globals).expect("rendering failed");
+    let reference_path = golden_dir().join(&case.name).with_extension("png");
+
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        frame.save(&reference_path).expect("could not write the reference image");
+        return frame;
+    }
+
+    let reference = match image::open(&reference_path) {
+        Ok(reference) => reference.to_rgba8(),
+        Err(error) => {
+            failures.push(format!(
+                "{}: could not read {}, run with UPDATE_GOLDEN=1 to create it ({error})",
+                case.name,
+                reference_path.display()
+            ));
+            return frame;
+        }
+    };
+
+    if let Err(message) = compare(&frame, &reference) {
+        let (actual_path, diff_path) = write_failure(&case.name, &frame, &reference);
+        failures.push(format!(
+            "{}: {message}, see {} and {}",
+            case.name,
+            actual_path.display(),
+            diff_path.display()
+        ));
+    }
+    frame
+}
+
+fn golden_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("golden")
+}
+
+fn channels_match(actual: &image::Rgba<u8>, expected: &image::Rgba<u8>) -> bool {
+    actual
+        .0
+        .iter()
+        .zip(expected.0.iter())
+        .all(|(a, e)| a.abs_diff(*e) <= CHANNEL_TOLERANCE)
+}
+
+fn compare(actual: &image::RgbaImage, reference: &image::RgbaImage) -> Result<(), String> {
+    if actual.dimensions() != reference.dimensions() {
+        return Err(format!("size is {:?} instead of {:?}", actual.dimensions(), reference.dimensions()));
+    }
+
+    let different_pixels = actual
+        .pixels()
+        .zip(reference.pixels())
+        .filter(|(actual, expected)| !channels_match(actual, expected))
+        .count();
+    let share = different_pixels as f64 / (actual.width() * actual.height()) as f64;
+    match share <= MAX_DIFFERENT_PIXELS {
+        true => Ok(()),
+        false => Err(format!("{different_pixels} pixels ({:.2}%) differ", share * 100.)),
+    }
+}
+
+/// writes the rendered frame and a diff image that marks the differing pixels red on top of the dimmed reference
+fn write_failure(name: &str, actual: &image::RgbaImage, reference: &image::RgbaImage) -> (PathBuf, PathBuf) {
+    let output_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("golden");
+    std::fs::create_dir_all(&output_dir).expect("could not create the output directory");
+
+    let actual_path = output_dir.join(format!("{name}.actual.png"));
+    actual.save(&actual_path).expect("could not write the rendered frame");
+
+    let diff = image::RgbaImage::from_fn(actual.width(), actual.height(), |x, y| {
+        let expected = reference.get_pixel_checked(x, y);
+        match expected {
+            Some(expected) if channels_match(actual.get_pixel(x, y), expected) => {
+                let luma = (expected.0[0] as u16 + expected.0[1] as u16 + expected.0[2] as u16) / 3 / 4;
+                image::Rgba([luma as u8, luma as u8, luma as u8, 255])
+            }
+            _ => image::Rgba([255, 0, 0, 255]),
+        }
+    });
+    let diff_path = output_dir.join(format!("{name}.diff.png"));
+    diff.save(&diff_path).expect("could not write the diff image");
+
+    (actual_path, diff_path)
+}
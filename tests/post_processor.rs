@@ -0,0 +1,94 @@
+//! effect indices, frame sizes and the creation of the effects of the post processor, which runs on the software adapter
+
+use shader_post_processing_example::headless::{HeadlessError, HeadlessRenderer, TEXTURE_FORMAT};
+use shader_post_processing_example::lut::{self, CubeLut};
+use shader_post_processing_example::post_processor::{PostProcessor, UnknownEffect};
+use shader_post_processing_example::Globals;
+
+const WIDTH: u32 = 8;
+const HEIGHT: u32 = 4;
+
+fn renderer() -> HeadlessRenderer {
+    pollster::block_on(HeadlessRenderer::new(WIDTH, HEIGHT, true)).expect("the post processor tests need the fallback adapter")
+}
+
+#[test]
+fn effects_are_created_when_they_enter_the_chain() {
+    let renderer = renderer();
+    let size = wgpu::Extent3d {
+        width: WIDTH,
+        height: HEIGHT,
+        depth_or_array_layers: 1,
+    };
+    let input = renderer.device().create_texture(&wgpu::TextureDescriptor {
+        label: Some("post processor input"),
+        size,
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: TEXTURE_FORMAT,
+        usage: wgpu::TextureUsages::TEXTURE_BINDING,
+        view_formats: &[],
+    });
+    let input_view = input.create_view(&wgpu::TextureViewDescriptor::default());
+    let mut post_processor = PostProcessor::new(renderer.device(), TEXTURE_FORMAT, &input_view, size);
+
+    let vignette = post_processor.effect_index("vignette").expect("built in effect");
+    let droplet = post_processor.effect_index("droplet").expect("built in effect");
+    assert!((0..post_processor.effect_count()).all(|effect_index| post_processor.effect(effect_index).is_none()));
+
+    post_processor
+        .set_chain(renderer.device(), renderer.queue(), vec![vignette])
+        .expect("the index is registered");
+    assert!(post_processor.effect(vignette).is_some());
+    assert!(post_processor.effect(droplet).is_none());
+
+    let parameters = post_processor
+        .create_effect(renderer.device(), renderer.queue(), droplet)
+        .expect("the index is registered")
+        .parameters();
+    assert!(parameters.is_some());
+    assert!(post_processor.effect(droplet).is_some());
+}
+
+#[test]
+fn unknown_effect_indices_are_errors() {
+    let mut renderer = renderer();
+    let unknown = renderer.effect_count();
+    let vignette = renderer.effect_index("vignette").expect("built in effect");
+
+    assert!(matches!(
+        renderer.render(&[vignette, unknown], &Globals::new()),
+        Err(HeadlessError::UnknownEffect(UnknownEffect(effect_index))) if effect_index == unknown
+    ));
+    assert!(renderer.parameters(unknown).is_none());
+    assert!(matches!(
+        renderer.set_parameter(unknown, "intensity", &[1.]),
+        Err(HeadlessError::UnknownEffect(_))
+    ));
+    let lut = CubeLut::parse(lut::DEFAULT_LUT).expect("the default table is valid");
+    assert!(matches!(renderer.set_lut(unknown, &lut), Err(HeadlessError::UnknownEffect(_))));
+
+    // the renderer still works with valid indices afterwards
+    renderer.render(&[vignette], &Globals::new()).expect("rendering failed");
+}
+
+#[test]
+fn empty_frames_are_errors() {
+    assert!(matches!(
+        pollster::block_on(HeadlessRenderer::new(0, HEIGHT, true)),
+        Err(HeadlessError::InvalidSize { width: 0, height: HEIGHT })
+    ));
+
+    let mut renderer = renderer();
+    assert!(matches!(renderer.resize(WIDTH, 0), Err(HeadlessError::InvalidSize { .. })));
+    let empty = image::DynamicImage::new_rgba8(0, 0);
+    assert!(matches!(
+        renderer.set_scene_image(&empty, None),
+        Err(HeadlessError::InvalidSize { .. })
+    ));
+
+    // the renderer keeps its size
+    let frame = renderer.render(&[], &Globals::new()).expect("rendering failed");
+    assert_eq!(frame.dimensions(), (WIDTH, HEIGHT));
+}
@@ -0,0 +1,111 @@
+//! parses, serializes and applies presets, the post processor runs on the software adapter
+
+use shader_post_processing_example::config::{ConfigError, ParameterValue};
+use shader_post_processing_example::headless::{HeadlessRenderer, TEXTURE_FORMAT};
+use shader_post_processing_example::post_processor::PostProcessor;
+use shader_post_processing_example::preset::Preset;
+use shader_post_processing_example::EffectRegistry;
+
+#[test]
+fn serialized_presets_are_parsed_back() {
+    let preset = Preset {
+        name: "neon \"night\"".to_string(),
+        effects: vec!["edge_sobel".to_string(), "vignette".to_string()],
+        parameters: vec![
+            ParameterValue {
+                effect: "edge_sobel".to_string(),
+                name: "color".to_string(),
+                values: vec![0.1, 1., 0.75],
+            },
+            ParameterValue {
+                effect: "vignette".to_string(),
+                name: "intensity".to_string(),
+                values: vec![0.35],
+            },
+        ],
+        cursor: Some((1., -0.25)),
+        time: Some(2.5),
+    };
+    assert_eq!(
+        Preset::parse(&preset.to_toml(), &EffectRegistry::built_in()).expect("the serialized preset is valid"),
+        preset
+    );
+
+    // effects that are added at runtime can have names that are not bare keys
+    let mut effect_registry = EffectRegistry::built_in();
+    effect_registry.register_name("my tint");
+    effect_registry.register_name("tint.v2");
+    let preset = Preset {
+        name: "tints".to_string(),
+        effects: vec!["my tint".to_string(), "tint.v2".to_string()],
+        parameters: vec![
+            ParameterValue {
+                effect: "my tint".to_string(),
+                name: "color".to_string(),
+                values: vec![1., 0.5, 0.],
+            },
+            ParameterValue {
+                effect: "tint.v2".to_string(),
+                name: "strength.red".to_string(),
+                values: vec![0.25],
+            },
+        ],
+        cursor: None,
+        time: None,
+    };
+    assert_eq!(
+        Preset::parse(&preset.to_toml(), &effect_registry).expect("the serialized preset is valid"),
+        preset
+    );
+}
+
+#[test]
+fn built_in_presets_are_valid() {
+    assert!(!Preset::built_in().is_empty());
+}
+
+#[test]
+fn parameters_of_other_effects_are_rejected() {
+    match Preset::parse(
+        "effects = [\"droplet\"]\n\n[parameters.vignette]\nintensity = 1",
+        &EffectRegistry::built_in(),
+    ) {
+        Err(ConfigError::Invalid { line, key, .. }) => assert_eq!((line, key.as_str()), (4, "parameters.vignette.intensity")),
+        result => panic!("expected an invalid preset but got {:?}", result),
+    }
+}
+
+#[test]
+fn captured_presets_contain_the_applied_values() {
+    let renderer = pollster::block_on(HeadlessRenderer::new(8, 4, true)).expect("the preset tests need the fallback adapter");
+    let size = wgpu::Extent3d {
+        width: 8,
+        height: 4,
+        depth_or_array_layers: 1,
+    };
+    let input = renderer.device().create_texture(&wgpu::TextureDescriptor {
+        label: Some("preset input"),
+        size,
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: TEXTURE_FORMAT,
+        usage: wgpu::TextureUsages::TEXTURE_BINDING,
+        view_formats: &[],
+    });
+    let input_view = input.create_view(&wgpu::TextureViewDescriptor::default());
+    let mut post_processor = PostProcessor::new(renderer.device(), TEXTURE_FORMAT, &input_view, size);
+
+    for preset in Preset::built_in() {
+        preset
+            .apply(renderer.device(), renderer.queue(), &mut post_processor)
+            .expect("the built-in presets only use built-in effects");
+        let captured = Preset::capture(preset.name.clone(), &post_processor);
+        assert_eq!(captured.effects, preset.effects);
+        assert_eq!(captured.cursor, preset.cursor);
+        assert_eq!(captured.time, None);
+        for parameter in &preset.parameters {
+            assert!(captured.parameters.contains(parameter), "{:?} was not applied", parameter);
+        }
+    }
+}
@@ -0,0 +1,66 @@
+//! renders the scene sources without effects on the software adapter
+
+use shader_post_processing_example::headless::{HeadlessRenderer, TEXTURE_FORMAT};
+use shader_post_processing_example::scene::{FrameBufferScene, ProceduralScene};
+use shader_post_processing_example::Globals;
+
+const WIDTH: u32 = 8;
+const HEIGHT: u32 = 4;
+
+fn renderer() -> HeadlessRenderer {
+    pollster::block_on(HeadlessRenderer::new(WIDTH, HEIGHT, true)).expect("the scene tests need the fallback adapter")
+}
+
+#[test]
+fn frame_buffer_scene_shows_its_pixels() {
+    let mut renderer = renderer();
+    let mut scene = FrameBufferScene::new(
+        renderer.device(),
+        renderer.queue(),
+        TEXTURE_FORMAT,
+        renderer.globals_bind_group_layout(),
+        WIDTH,
+        HEIGHT,
+    );
+    for (x, y, pixel) in scene.frame_mut().enumerate_pixels_mut() {
+        *pixel = image::Rgba([(x * 30) as u8, (y * 60) as u8, 200, 255]);
+    }
+    let expected = scene.frame().clone();
+    renderer.set_scene(Box::new(scene));
+    let frame = renderer.render(&[], &Globals::new()).expect("rendering failed");
+    assert_frame_eq(&frame, &expected);
+}
+
+/// the pixels are converted to linear colors and back, which may round differently
+fn assert_frame_eq(actual: &image::RgbaImage, expected: &image::RgbaImage) {
+    for (actual, expected) in actual.pixels().zip(expected.pixels()) {
+        for channel in 0..4 {
+            assert!(actual[channel].abs_diff(expected[channel]) <= 1, "{:?} != {:?}", actual, expected);
+        }
+    }
+}
+
+#[test]
+fn procedural_scene_is_drawn_by_its_fragment_shader() {
+    let mut renderer = renderer();
+    let source = r#"
+#include "prelude.wgsl"
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(1.0, 0.0, 0.0, 1.0);
+}
+"#;
+    let scene = ProceduralScene::new(
+        renderer.device(),
+        TEXTURE_FORMAT,
+        renderer.globals_bind_group_layout(),
+        "scene_red.wgsl",
+        source,
+    )
+    .expect("the prelude is embedded");
+    renderer.set_scene(Box::new(scene));
+
+    let frame = renderer.render(&[], &Globals::new()).expect("rendering failed");
+    assert!(frame.pixels().all(|pixel| pixel.0 == [255, 0, 0, 255]));
+}